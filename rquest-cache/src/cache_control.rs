@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use http::header::CACHE_CONTROL;
+use http::HeaderMap;
+
+/// The `Cache-Control` directives this cache understands, from a request or a response.
+///
+/// Unknown directives are ignored, as are directives only meant for shared caches (`s-maxage`,
+/// `proxy-revalidate`...) since this is a private cache.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct CacheControl {
+    pub(crate) max_age: Option<Duration>,
+    pub(crate) no_cache: bool,
+    pub(crate) no_store: bool,
+    pub(crate) must_revalidate: bool,
+    /// Request only: accept stale responses, by at most the given duration if any.
+    pub(crate) max_stale: Option<Option<Duration>>,
+    /// Request only: the response must stay fresh for at least this long.
+    pub(crate) min_fresh: Option<Duration>,
+    /// Request only: answer from the cache or fail with `504 Gateway Timeout`.
+    pub(crate) only_if_cached: bool,
+}
+
+impl CacheControl {
+    /// Parses every `Cache-Control` header in `headers`.
+    pub(crate) fn parse(headers: &HeaderMap) -> Self {
+        let mut cache_control = Self::default();
+        let directives = headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+
+        for directive in directives {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name, Some(value.trim().trim_matches('"'))),
+                None => (directive, None),
+            };
+            let seconds = value.and_then(parse_seconds);
+
+            match name.trim().to_ascii_lowercase().as_str() {
+                // An invalid max-age makes the response stale (RFC 9111 section 4.2.1).
+                "max-age" => cache_control.max_age = Some(seconds.unwrap_or_default()),
+                "no-cache" => cache_control.no_cache = true,
+                "no-store" => cache_control.no_store = true,
+                "must-revalidate" => cache_control.must_revalidate = true,
+                "max-stale" => cache_control.max_stale = Some(seconds),
+                "min-fresh" => cache_control.min_fresh = seconds,
+                "only-if-cached" => cache_control.only_if_cached = true,
+                _ => {}
+            }
+        }
+        cache_control
+    }
+}
+
+/// Parses delta-seconds, saturating on overflow as allowed by RFC 9111 section 1.2.2.
+pub(crate) fn parse_seconds(value: &str) -> Option<Duration> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(Duration::from_secs(value.parse().unwrap_or(u64::MAX)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(values: &[&str]) -> CacheControl {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(CACHE_CONTROL, value.parse().unwrap());
+        }
+        CacheControl::parse(&headers)
+    }
+
+    #[test]
+    fn parses_directives_across_headers() {
+        let cache_control = parse(&["Max-Age=\"60\", must-revalidate", "no-cache"]);
+        assert_eq!(cache_control.max_age, Some(Duration::from_secs(60)));
+        assert!(cache_control.must_revalidate);
+        assert!(cache_control.no_cache);
+        assert!(!cache_control.no_store);
+    }
+
+    #[test]
+    fn invalid_max_age_is_zero() {
+        assert_eq!(parse(&["max-age=soon"]).max_age, Some(Duration::ZERO));
+        assert_eq!(
+            parse(&["max-age=99999999999999999999999"]).max_age,
+            Some(Duration::from_secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn parses_request_directives() {
+        let cache_control = parse(&["max-stale, min-fresh=10, only-if-cached"]);
+        assert_eq!(cache_control.max_stale, Some(None));
+        assert_eq!(cache_control.min_fresh, Some(Duration::from_secs(10)));
+        assert!(cache_control.only_if_cached);
+        assert_eq!(
+            parse(&["max-stale=5"]).max_stale,
+            Some(Some(Duration::from_secs(5)))
+        );
+    }
+}
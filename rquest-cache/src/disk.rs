@@ -0,0 +1,102 @@
+use std::convert::TryFrom;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::entry::CachedResponse;
+use crate::error::CacheError;
+use crate::storage::CacheStorage;
+
+/// Numbers the temporary files of every storage in the process, so that storages sharing a
+/// directory never write to the same one.
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// A [`CacheStorage`] keeping one file per entry in a directory, so the cache survives restarts.
+///
+/// Entries are written with [`CachedResponse::encode`] to a temporary file which is then renamed,
+/// so concurrent readers never see a partially written entry. Nothing is ever evicted: clean the
+/// directory up if it grows too large.
+#[derive(Debug)]
+pub struct DiskStorage {
+    dir: PathBuf,
+}
+
+impl DiskStorage {
+    /// Creates a storage in `dir`, which is created on the first write if it doesn't exist.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The directory holding the entries.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.entry", fnv1a(key.as_bytes())))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl CacheStorage for DiskStorage {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, CacheError> {
+        let contents = match tokio::fs::read(self.path(key)).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        // The file starts with the full key, file names are only hashes and could collide.
+        if contents.len() < 4 {
+            return Err(CacheError::InvalidEntry("truncated entry"));
+        }
+        let (key_len, rest) = contents.split_at(4);
+        let key_len = u32::from_be_bytes([key_len[0], key_len[1], key_len[2], key_len[3]]);
+        let key_len = key_len as usize;
+        if rest.len() < key_len {
+            return Err(CacheError::InvalidEntry("truncated entry"));
+        }
+        let (stored_key, entry) = rest.split_at(key_len);
+        if stored_key != key.as_bytes() {
+            return Ok(None);
+        }
+        CachedResponse::decode(entry).map(Some)
+    }
+
+    async fn put(&self, key: &str, entry: CachedResponse) -> Result<(), CacheError> {
+        let key_len =
+            u32::try_from(key.len()).map_err(|_| CacheError::InvalidEntry("key too long"))?;
+        let mut contents = key_len.to_be_bytes().to_vec();
+        contents.extend_from_slice(key.as_bytes());
+        contents.extend_from_slice(&entry.encode());
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let temp = self.dir.join(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&temp, contents).await?;
+        if let Err(err) = tokio::fs::rename(&temp, self.path(key)).await {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 64-bit FNV-1a, a hash which is stable across builds and platforms unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,503 @@
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use http::header::{
+    HeaderName, AGE, CONNECTION, CONTENT_LENGTH, DATE, ETAG, EXPIRES, LAST_MODIFIED,
+    PROXY_AUTHENTICATE, PROXY_AUTHORIZATION, TE, TRAILER, TRANSFER_ENCODING, UPGRADE, VARY,
+};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use rquest::Url;
+use rquest_middleware::Response;
+
+use crate::cache_control::{parse_seconds, CacheControl};
+use crate::error::CacheError;
+
+/// Identifies the encoding of [`CachedResponse::encode`].
+const MAGIC: &[u8] = b"RQC\x02";
+
+/// The largest `Age` taken into account, larger values are clamped to it (RFC 9111 section
+/// 1.2.2).
+const MAX_AGE_VALUE: Duration = Duration::from_secs(2_147_483_648);
+
+/// Headers which only make sense for a single connection and are never stored
+/// (RFC 9110 section 7.6.1).
+const HOP_BY_HOP: [HeaderName; 7] = [
+    CONNECTION,
+    PROXY_AUTHENTICATE,
+    PROXY_AUTHORIZATION,
+    TE,
+    TRAILER,
+    TRANSFER_ENCODING,
+    UPGRADE,
+];
+
+/// A response stored in a [`CacheStorage`](crate::CacheStorage).
+///
+/// Storages which can't keep it in memory can use [`encode`](Self::encode) and
+/// [`decode`](Self::decode) to turn it into bytes and back.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    url: Url,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    request_time: SystemTime,
+    response_time: SystemTime,
+    /// The request headers named by `Vary` when the response was stored, to select it only for
+    /// matching requests.
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+
+impl CachedResponse {
+    /// Creates an entry with an empty body for the response to a request with `request_headers`.
+    /// `request_time` and `response_time` are when the request was sent and its response
+    /// received.
+    ///
+    /// Returns `None` if the response varies on `*`, as it can never be reused.
+    pub(crate) fn new(
+        request_headers: &HeaderMap,
+        res: &Response,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) -> Option<Self> {
+        let mut vary = Vec::new();
+        for name in vary_names(res.headers()) {
+            let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+            vary.push((name.clone(), request_headers.get(name).cloned()));
+        }
+
+        let mut headers = res.headers().clone();
+        strip_hop_by_hop(&mut headers);
+        Some(Self {
+            url: res.url().clone(),
+            status: res.status(),
+            headers,
+            body: Bytes::new(),
+            request_time,
+            response_time,
+            vary,
+        })
+    }
+
+    /// Sets the body of the entry.
+    pub(crate) fn with_body(mut self, body: Bytes) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// The `Url` of the response.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The body of the response.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Returns `true` if the entry can be used for a request with `request_headers`, i.e. the
+    /// headers named by `Vary` have the same values as for the request which got the response.
+    pub(crate) fn matches(&self, request_headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| request_headers.get(name) == value.as_ref())
+    }
+
+    pub(crate) fn cache_control(&self) -> CacheControl {
+        CacheControl::parse(&self.headers)
+    }
+
+    pub(crate) fn etag(&self) -> Option<&HeaderValue> {
+        self.headers.get(ETAG)
+    }
+
+    pub(crate) fn last_modified(&self) -> Option<&HeaderValue> {
+        self.headers.get(LAST_MODIFIED)
+    }
+
+    /// How long the response is fresh for after it was generated (RFC 9111 section 4.2.1).
+    pub(crate) fn freshness_lifetime(&self) -> Duration {
+        if let Some(max_age) = self.cache_control().max_age {
+            return max_age;
+        }
+
+        let date = self.date();
+        if self.headers.contains_key(EXPIRES) {
+            // An invalid Expires means the response is already stale.
+            return header_date(&self.headers, EXPIRES)
+                .and_then(|expires| expires.duration_since(date).ok())
+                .unwrap_or_default();
+        }
+
+        // Heuristic freshness, 10% of the time since the last modification (section 4.2.2).
+        match header_date(&self.headers, LAST_MODIFIED) {
+            Some(last_modified) if is_heuristically_cacheable(self.status) => date
+                .duration_since(last_modified)
+                .map(|age| age / 10)
+                .unwrap_or_default(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// The age of the response at `now` (RFC 9111 section 4.2.3).
+    pub(crate) fn current_age(&self, now: SystemTime) -> Duration {
+        let age_value = self
+            .headers
+            .get(AGE)
+            .and_then(|age| age.to_str().ok())
+            .and_then(parse_seconds)
+            .map_or(Duration::ZERO, |age| age.min(MAX_AGE_VALUE));
+
+        let apparent_age = since(self.response_time, self.date());
+        let response_delay = since(self.response_time, self.request_time);
+        let corrected_initial_age = apparent_age.max(age_value.saturating_add(response_delay));
+        let resident_time = since(now, self.response_time);
+        corrected_initial_age.saturating_add(resident_time)
+    }
+
+    /// Updates the entry with the headers of a `304 Not Modified` response validating it
+    /// (RFC 9111 section 4.3.4).
+    pub(crate) fn revalidated(
+        &mut self,
+        not_modified: &Response,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) {
+        let mut headers = not_modified.headers().clone();
+        strip_hop_by_hop(&mut headers);
+        headers.remove(CONTENT_LENGTH);
+
+        let mut last = None;
+        for (name, value) in headers {
+            // `HeaderMap::into_iter` only yields the name for the first of several values.
+            let name = match name {
+                Some(name) => {
+                    self.headers.remove(&name);
+                    last = Some(name.clone());
+                    name
+                }
+                None => last.clone().expect("the first value comes with a name"),
+            };
+            self.headers.append(name, value);
+        }
+        self.request_time = request_time;
+        self.response_time = response_time;
+    }
+
+    /// Builds the response to return to the caller, with its `Age` at `now`.
+    pub(crate) fn to_response(&self, now: SystemTime) -> rquest_middleware::Result<Response> {
+        let mut response = Response::builder()
+            .status(self.status)
+            .url(self.url.clone())
+            .body(self.body.clone())?;
+        *response.headers_mut() = self.headers.clone();
+        response
+            .headers_mut()
+            .insert(AGE, HeaderValue::from(self.current_age(now).as_secs()));
+        Ok(response)
+    }
+
+    fn date(&self) -> SystemTime {
+        header_date(&self.headers, DATE).unwrap_or(self.response_time)
+    }
+
+    /// Encodes the entry into bytes, to be decoded with [`CachedResponse::decode`].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        put_bytes(&mut buf, self.url.as_str().as_bytes());
+        buf.extend_from_slice(&self.status.as_u16().to_be_bytes());
+        put_time(&mut buf, self.request_time);
+        put_time(&mut buf, self.response_time);
+
+        put_len(&mut buf, self.headers.len());
+        for (name, value) in &self.headers {
+            put_bytes(&mut buf, name.as_str().as_bytes());
+            put_bytes(&mut buf, value.as_bytes());
+        }
+
+        put_len(&mut buf, self.vary.len());
+        for (name, value) in &self.vary {
+            put_bytes(&mut buf, name.as_str().as_bytes());
+            match value {
+                Some(value) => {
+                    buf.push(1);
+                    put_bytes(&mut buf, value.as_bytes());
+                }
+                None => buf.push(0),
+            }
+        }
+
+        put_bytes(&mut buf, &self.body);
+        buf
+    }
+
+    /// Decodes an entry encoded with [`CachedResponse::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`CacheError::InvalidEntry`] if `bytes` is not a valid encoded entry.
+    pub fn decode(bytes: &[u8]) -> Result<Self, CacheError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(CacheError::InvalidEntry("unknown format"));
+        }
+
+        let url = std::str::from_utf8(reader.bytes()?)
+            .ok()
+            .and_then(|url| Url::parse(url).ok())
+            .ok_or(CacheError::InvalidEntry("invalid url"))?;
+        let status = reader.take(2)?;
+        let status = StatusCode::from_u16(u16::from_be_bytes([status[0], status[1]]))
+            .map_err(|_| CacheError::InvalidEntry("invalid status"))?;
+        let request_time = reader.time()?;
+        let response_time = reader.time()?;
+
+        let mut headers = HeaderMap::new();
+        for _ in 0..reader.len()? {
+            let name = reader.header_name()?;
+            let value = reader.header_value()?;
+            headers.append(name, value);
+        }
+
+        let mut vary = Vec::new();
+        for _ in 0..reader.len()? {
+            let name = reader.header_name()?;
+            let value = match reader.take(1)? {
+                [0] => None,
+                _ => Some(reader.header_value()?),
+            };
+            vary.push((name, value));
+        }
+
+        let body = Bytes::copy_from_slice(reader.bytes()?);
+        Ok(Self {
+            url,
+            status,
+            headers,
+            body,
+            request_time,
+            response_time,
+            vary,
+        })
+    }
+}
+
+/// The header names listed in `Vary`, lowercased.
+pub(crate) fn vary_names(headers: &HeaderMap) -> impl Iterator<Item = String> + '_ {
+    headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+}
+
+/// Statuses which can be cached without explicit freshness information (RFC 9110 section
+/// 15.1).
+pub(crate) fn is_heuristically_cacheable(status: StatusCode) -> bool {
+    matches!(
+        status.as_u16(),
+        200 | 203 | 204 | 206 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+    )
+}
+
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let listed: Vec<HeaderName> = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    for name in HOP_BY_HOP.iter().chain(&listed) {
+        headers.remove(name);
+    }
+}
+
+fn header_date(headers: &HeaderMap, name: HeaderName) -> Option<SystemTime> {
+    let value = headers.get(name)?.to_str().ok()?;
+    httpdate::parse_http_date(value).ok()
+}
+
+/// The time elapsed from `earlier` to `later`, zero if `earlier` is actually later.
+fn since(later: SystemTime, earlier: SystemTime) -> Duration {
+    later.duration_since(earlier).unwrap_or_default()
+}
+
+fn put_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u64).to_be_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    put_len(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+fn put_time(buf: &mut Vec<u8>, time: SystemTime) {
+    let since_epoch = since(time, UNIX_EPOCH);
+    buf.extend_from_slice(&since_epoch.as_secs().to_be_bytes());
+    buf.extend_from_slice(&since_epoch.subsec_nanos().to_be_bytes());
+}
+
+/// Reads the fields written by [`CachedResponse::encode`].
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CacheError> {
+        if self.0.len() < len {
+            return Err(CacheError::InvalidEntry("truncated entry"));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn len(&mut self) -> Result<usize, CacheError> {
+        let mut len = [0; 8];
+        len.copy_from_slice(self.take(8)?);
+        usize::try_from(u64::from_be_bytes(len))
+            .map_err(|_| CacheError::InvalidEntry("entry too large"))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], CacheError> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn time(&mut self) -> Result<SystemTime, CacheError> {
+        let mut secs = [0; 8];
+        secs.copy_from_slice(self.take(8)?);
+        let mut nanos = [0; 4];
+        nanos.copy_from_slice(self.take(4)?);
+        let since_epoch = Duration::new(u64::from_be_bytes(secs), u32::from_be_bytes(nanos));
+        UNIX_EPOCH
+            .checked_add(since_epoch)
+            .ok_or(CacheError::InvalidEntry("invalid time"))
+    }
+
+    fn header_name(&mut self) -> Result<HeaderName, CacheError> {
+        HeaderName::from_bytes(self.bytes()?)
+            .map_err(|_| CacheError::InvalidEntry("invalid header name"))
+    }
+
+    fn header_value(&mut self) -> Result<HeaderValue, CacheError> {
+        HeaderValue::from_bytes(self.bytes()?)
+            .map_err(|_| CacheError::InvalidEntry("invalid header value"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(headers: &[(&str, &str)]) -> CachedResponse {
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert("accept", HeaderValue::from_static("application/json"));
+
+        let mut builder = Response::builder().url("https://example.com/data".parse().unwrap());
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let res = builder.body("data").unwrap();
+
+        let time = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        CachedResponse::new(&request_headers, &res, time, time)
+            .unwrap()
+            .with_body(Bytes::from_static(b"data"))
+    }
+
+    #[test]
+    fn freshness_lifetime_precedence() {
+        let date = "Mon, 12 Jan 1970 13:46:40 GMT";
+        let entry = |headers: &[(&str, &str)]| entry(headers).freshness_lifetime();
+
+        assert_eq!(
+            entry(&[
+                ("cache-control", "max-age=60"),
+                ("date", date),
+                ("expires", "Mon, 12 Jan 1970 13:56:40 GMT"),
+            ]),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            entry(&[("date", date), ("expires", "Mon, 12 Jan 1970 13:56:40 GMT")]),
+            Duration::from_secs(600)
+        );
+        assert_eq!(entry(&[("date", date), ("expires", "0")]), Duration::ZERO);
+        assert_eq!(
+            entry(&[
+                ("date", date),
+                ("last-modified", "Mon, 12 Jan 1970 11:00:00 GMT")
+            ]),
+            Duration::from_secs(1000)
+        );
+        assert_eq!(entry(&[]), Duration::ZERO);
+    }
+
+    #[test]
+    fn current_age_includes_age_header_and_resident_time() {
+        let entry = entry(&[("age", "30")]);
+        let now = entry.response_time + Duration::from_secs(10);
+        assert_eq!(entry.current_age(now), Duration::from_secs(40));
+    }
+
+    #[test]
+    fn current_age_clamps_oversized_age_header() {
+        for age in ["18446744073709551615", "99999999999999999999999"] {
+            let entry = entry(&[("age", age)]);
+            let now = entry.response_time + Duration::from_secs(10);
+            assert_eq!(
+                entry.current_age(now),
+                MAX_AGE_VALUE + Duration::from_secs(10)
+            );
+            let res = entry.to_response(now).unwrap();
+            assert_eq!(res.headers()[AGE], "2147483658");
+        }
+    }
+
+    #[test]
+    fn vary_selects_matching_requests() {
+        let entry = entry(&[("vary", "Accept, Accept-Language")]);
+        let mut request_headers = HeaderMap::new();
+        assert!(!entry.matches(&request_headers));
+
+        request_headers.insert("accept", HeaderValue::from_static("application/json"));
+        assert!(entry.matches(&request_headers));
+
+        request_headers.insert("accept-language", HeaderValue::from_static("fr"));
+        assert!(!entry.matches(&request_headers));
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let entry = entry(&[
+            ("etag", "\"v1\""),
+            ("set-cookie", "a=1"),
+            ("set-cookie", "b=2"),
+            ("vary", "accept, accept-language"),
+        ]);
+        let decoded = CachedResponse::decode(&entry.encode()).unwrap();
+
+        assert_eq!(decoded.url, entry.url);
+        assert_eq!(decoded.status, entry.status);
+        assert_eq!(decoded.headers, entry.headers);
+        assert_eq!(decoded.body, entry.body);
+        assert_eq!(decoded.request_time, entry.request_time);
+        assert_eq!(decoded.vary, entry.vary);
+
+        assert!(CachedResponse::decode(&entry.encode()[..20]).is_err());
+        assert!(CachedResponse::decode(b"nope").is_err());
+    }
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+/// Errors returned by [`CacheStorage`](crate::CacheStorage) implementations.
+///
+/// [`CacheMiddleware`](crate::CacheMiddleware) never fails a request because of them: the cache
+/// is bypassed and the error logged instead.
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("I/O error in the cache storage")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid cache entry: {0}")]
+    InvalidEntry(&'static str),
+    #[error(transparent)]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
@@ -0,0 +1,38 @@
+//! HTTP caching middleware built on [`rquest_middleware`], following
+//! [RFC 9111](https://www.rfc-editor.org/rfc/rfc9111).
+//!
+//! [`CacheMiddleware`] answers `GET` requests from a [`CacheStorage`] while the stored response is
+//! fresh, revalidates it with the server once stale, and marks every response with a
+//! [`CacheStatus`] extension. Responses are kept in memory with [`MemoryStorage`] or on disk with
+//! `DiskStorage`; implement [`CacheStorage`] to keep them anywhere else.
+//!
+//! ## Example
+//!
+//! ```
+//! use rquest_cache::{CacheMiddleware, CacheStatus};
+//! use rquest_middleware::ClientBuilder;
+//!
+//! async fn run() {
+//!     let client = ClientBuilder::new(rquest::Client::new())
+//!         .with(CacheMiddleware::default())
+//!         .build();
+//!
+//!     let res = client.get("https://truelayer.com").send().await.unwrap();
+//!     println!("{:?}", res.extensions().get::<CacheStatus>());
+//! }
+//! ```
+
+mod cache_control;
+#[cfg(not(target_arch = "wasm32"))]
+mod disk;
+mod entry;
+mod error;
+mod middleware;
+mod storage;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use disk::DiskStorage;
+pub use entry::CachedResponse;
+pub use error::CacheError;
+pub use middleware::{CacheMiddleware, CacheStatus};
+pub use storage::{CacheStorage, MemoryStorage};
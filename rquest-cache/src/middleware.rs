@@ -0,0 +1,320 @@
+//! `CacheMiddleware` answers requests from a [`CacheStorage`] when it can.
+use std::num::NonZeroUsize;
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use http::header::{
+    EXPIRES, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, IF_UNMODIFIED_SINCE,
+};
+use http::{Extensions, HeaderMap, Method, StatusCode};
+use rquest::{Request, Url};
+use rquest_middleware::{Middleware, Next, Response, Result};
+
+use crate::cache_control::CacheControl;
+use crate::entry::{is_heuristically_cacheable, CachedResponse};
+use crate::storage::{CacheStorage, MemoryStorage};
+
+/// How many responses [`CacheMiddleware::default`] keeps in memory.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Responses with a longer body are not stored unless [`CacheMiddleware::max_body_size`] says
+/// otherwise.
+const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Whether a response was served from the cache, inserted in the extensions of every response
+/// returned by [`CacheMiddleware`].
+///
+/// ```
+/// use rquest_cache::{CacheMiddleware, CacheStatus};
+/// use rquest_middleware::ClientBuilder;
+///
+/// async fn run() {
+///     let client = ClientBuilder::new(rquest::Client::new())
+///         .with(CacheMiddleware::default())
+///         .build();
+///     let res = client.get("https://truelayer.com").send().await.unwrap();
+///     if res.extensions().get::<CacheStatus>() == Some(&CacheStatus::Hit) {
+///         println!("served from the cache");
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheStatus {
+    /// The response was fresh in the cache, no request was sent.
+    Hit,
+    /// The response comes from the server, the cache couldn't be used.
+    Miss,
+    /// The response was stale in the cache and the server confirmed it is still valid with a
+    /// `304 Not Modified`.
+    Revalidated,
+}
+
+/// A private HTTP cache following [RFC 9111](https://www.rfc-editor.org/rfc/rfc9111).
+///
+/// Only `GET` requests are answered from the cache, keyed by URL. A response is stored when it
+/// has explicit freshness information (`Cache-Control: max-age`, `Expires`) or a status which is
+/// cacheable by default, unless `Cache-Control: no-store` forbids it. It is then served:
+/// - as is while it is fresh, with its `Age`;
+/// - after revalidation with `If-None-Match` / `If-Modified-Since` once stale, when it has an
+///   `ETag` or `Last-Modified` validator.
+///
+/// The request `Cache-Control` directives `no-cache`, `max-age`, `max-stale`, `min-fresh` and
+/// `only-if-cached` are honoured, as well as `Vary`: a stored response is only used for requests
+/// with the same values for the headers it varies on. Successful requests with an unsafe method
+/// (`POST`, `PUT`, `DELETE`...) invalidate the response stored for their URL.
+///
+/// The body is only read into memory when the response is worth storing and not longer than
+/// [`max_body_size`](Self::max_body_size), other responses keep streaming to the caller.
+///
+/// Storage errors never fail a request, they are logged and the cache is bypassed. Requests which
+/// already carry conditional headers are sent as is, the caller handles the `304` itself.
+///
+/// ```
+/// use rquest_cache::{CacheMiddleware, DiskStorage};
+/// use rquest_middleware::ClientBuilder;
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(CacheMiddleware::new(DiskStorage::new("/tmp/http-cache")))
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct CacheMiddleware<S = MemoryStorage> {
+    storage: S,
+    max_body_size: usize,
+}
+
+impl<S: CacheStorage> CacheMiddleware<S> {
+    /// Creates a cache keeping responses in `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Responses with a body longer than `max_body_size` bytes are streamed to the caller and not
+    /// stored, 16MiB by default.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// The storage holding the responses.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    async fn load(&self, key: &str) -> Option<CachedResponse> {
+        match self.storage.get(key).await {
+            Ok(entry) => entry,
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(key, error = %_err, "Failed to read from the HTTP cache");
+                None
+            }
+        }
+    }
+
+    async fn store(&self, key: &str, entry: CachedResponse) {
+        if let Err(_err) = self.storage.put(key, entry).await {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(key, error = %_err, "Failed to write to the HTTP cache");
+        }
+    }
+
+    async fn invalidate(&self, key: &str) {
+        if let Err(_err) = self.storage.remove(key).await {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(key, error = %_err, "Failed to remove from the HTTP cache");
+        }
+    }
+}
+
+impl Default for CacheMiddleware<MemoryStorage> {
+    /// A cache keeping up to 1024 responses in memory.
+    fn default() -> Self {
+        let capacity = NonZeroUsize::new(DEFAULT_CAPACITY).expect("capacity is not zero");
+        Self::new(MemoryStorage::new(capacity))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<S: CacheStorage> Middleware for CacheMiddleware<S> {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let key = cache_key(req.url());
+        if req.method() != Method::GET {
+            let invalidates = !is_safe(req.method());
+            let mut res = next.run(req, extensions).await?;
+            if invalidates && (res.status().is_success() || res.status().is_redirection()) {
+                self.invalidate(&key).await;
+            }
+            res.extensions_mut().insert(CacheStatus::Miss);
+            return Ok(res);
+        }
+
+        let request_cache_control = CacheControl::parse(req.headers());
+        let stored = match self.load(&key).await {
+            Some(entry) if entry.matches(req.headers()) => Some(entry),
+            _ => None,
+        };
+
+        let now = SystemTime::now();
+        if let Some(entry) = &stored {
+            if is_usable(entry, &request_cache_control, now) {
+                let mut res = entry.to_response(now)?;
+                res.extensions_mut().insert(CacheStatus::Hit);
+                return Ok(res);
+            }
+        }
+
+        if request_cache_control.only_if_cached {
+            let mut res = Response::builder()
+                .status(StatusCode::GATEWAY_TIMEOUT)
+                .url(req.url().clone())
+                .body(Bytes::new())?;
+            res.extensions_mut().insert(CacheStatus::Miss);
+            return Ok(res);
+        }
+
+        let request_headers = req.headers().clone();
+        let revalidating = match &stored {
+            Some(entry) if !is_conditional(req.headers()) => add_validators(&mut req, entry),
+            _ => false,
+        };
+
+        let request_time = SystemTime::now();
+        let mut res = next.run(req, extensions).await?;
+        let response_time = SystemTime::now();
+        let response_cache_control = CacheControl::parse(res.headers());
+
+        if let Some(mut entry) =
+            stored.filter(|_| revalidating && res.status() == StatusCode::NOT_MODIFIED)
+        {
+            entry.revalidated(&res, request_time, response_time);
+            let mut revalidated = entry.to_response(response_time)?;
+            if response_cache_control.no_store || request_cache_control.no_store {
+                self.invalidate(&key).await;
+            } else {
+                self.store(&key, entry).await;
+            }
+            revalidated
+                .extensions_mut()
+                .insert(CacheStatus::Revalidated);
+            return Ok(revalidated);
+        }
+
+        if !request_cache_control.no_store && is_storable(&res, &response_cache_control) {
+            // The headers are enough to tell whether the body is worth reading.
+            let entry = CachedResponse::new(&request_headers, &res, request_time, response_time)
+                .filter(is_worth_storing);
+            match entry {
+                Some(entry) => {
+                    res = res.buffer_up_to(self.max_body_size).await?;
+                    // The body may have been buffered by another middleware already.
+                    match res.buffered_bytes() {
+                        Some(body) if body.len() <= self.max_body_size => {
+                            self.store(&key, entry.with_body(body.clone())).await
+                        }
+                        _ => self.invalidate(&key).await,
+                    }
+                }
+                None => self.invalidate(&key).await,
+            }
+        }
+        res.extensions_mut().insert(CacheStatus::Miss);
+        Ok(res)
+    }
+}
+
+/// Entries are keyed by URL, without the fragment which is never sent to the server.
+fn cache_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.into()
+}
+
+/// Safe methods don't change the state of the server (RFC 9110 section 9.2.1).
+fn is_safe(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Returns `true` if `entry` can be served without contacting the server (RFC 9111 section 4.2).
+fn is_usable(entry: &CachedResponse, request: &CacheControl, now: SystemTime) -> bool {
+    let response = entry.cache_control();
+    if request.no_cache || response.no_cache {
+        return false;
+    }
+
+    let age = entry.current_age(now);
+    if request.max_age.is_some_and(|max_age| age > max_age) {
+        return false;
+    }
+
+    let lifetime = entry.freshness_lifetime();
+    let required = age.saturating_add(request.min_fresh.unwrap_or_default());
+    if lifetime > required {
+        return true;
+    }
+
+    // The request can accept a stale response, unless the server forbids it.
+    let staleness = age.saturating_sub(lifetime);
+    match request.max_stale {
+        Some(Some(max_stale)) => !response.must_revalidate && staleness <= max_stale,
+        Some(None) => !response.must_revalidate,
+        None => false,
+    }
+}
+
+/// Returns `true` if the request already carries preconditions set by the caller.
+fn is_conditional(headers: &HeaderMap) -> bool {
+    [
+        IF_MATCH,
+        IF_NONE_MATCH,
+        IF_MODIFIED_SINCE,
+        IF_UNMODIFIED_SINCE,
+        IF_RANGE,
+    ]
+    .iter()
+    .any(|name| headers.contains_key(name))
+}
+
+/// Turns `req` into a conditional request validating `entry`, returns `false` if the entry has
+/// no validator.
+fn add_validators(req: &mut Request, entry: &CachedResponse) -> bool {
+    let etag = entry.etag().cloned();
+    let last_modified = entry.last_modified().cloned();
+    let headers = req.headers_mut();
+    if let Some(etag) = &etag {
+        headers.insert(IF_NONE_MATCH, etag.clone());
+    }
+    if let Some(last_modified) = &last_modified {
+        headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+    }
+    etag.is_some() || last_modified.is_some()
+}
+
+/// Returns `true` if RFC 9111 section 3 allows a private cache to store the response.
+fn is_storable(res: &Response, cache_control: &CacheControl) -> bool {
+    if cache_control.no_store || res.status() == StatusCode::PARTIAL_CONTENT {
+        return false;
+    }
+    cache_control.max_age.is_some()
+        || res.headers().contains_key(EXPIRES)
+        || is_heuristically_cacheable(res.status())
+}
+
+/// An entry which is already stale and can't be revalidated would never be used.
+fn is_worth_storing(entry: &CachedResponse) -> bool {
+    entry.freshness_lifetime() > Duration::ZERO
+        || entry.etag().is_some()
+        || entry.last_modified().is_some()
+}
@@ -0,0 +1,101 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use lru::LruCache;
+
+use crate::entry::CachedResponse;
+use crate::error::CacheError;
+
+/// Where [`CacheMiddleware`](crate::CacheMiddleware) keeps its responses.
+///
+/// Keys are opaque strings chosen by the middleware. Implementations are free to evict entries
+/// whenever they see fit, the middleware simply treats a missing entry as a cache miss.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait CacheStorage: 'static + Send + Sync {
+    /// Returns the entry stored under `key`, if any.
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, CacheError>;
+
+    /// Stores `entry` under `key`, replacing any previous entry.
+    async fn put(&self, key: &str, entry: CachedResponse) -> Result<(), CacheError>;
+
+    /// Removes the entry stored under `key`, if any.
+    async fn remove(&self, key: &str) -> Result<(), CacheError>;
+}
+
+/// An in-memory [`CacheStorage`] which evicts the least recently used entry once it holds
+/// `capacity` entries.
+#[derive(Debug)]
+pub struct MemoryStorage {
+    entries: Mutex<LruCache<String, CachedResponse>>,
+}
+
+impl MemoryStorage {
+    /// Creates a storage holding at most `capacity` entries.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl CacheStorage for MemoryStorage {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, CacheError> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned())
+    }
+
+    async fn put(&self, key: &str, entry: CachedResponse) -> Result<(), CacheError> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .put(key.to_owned(), entry);
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderMap;
+    use std::time::SystemTime;
+
+    fn entry(url: &str) -> CachedResponse {
+        let res = rquest_middleware::Response::builder()
+            .url(url.parse().unwrap())
+            .body("")
+            .unwrap();
+        let now = SystemTime::now();
+        CachedResponse::new(&HeaderMap::new(), &res, now, now).unwrap()
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let storage = MemoryStorage::new(NonZeroUsize::new(2).unwrap());
+        storage.put("a", entry("http://a.com/")).await.unwrap();
+        storage.put("b", entry("http://b.com/")).await.unwrap();
+        storage.get("a").await.unwrap();
+        storage.put("c", entry("http://c.com/")).await.unwrap();
+
+        assert!(storage.get("a").await.unwrap().is_some());
+        assert!(storage.get("b").await.unwrap().is_none());
+        assert!(storage.get("c").await.unwrap().is_some());
+
+        storage.remove("a").await.unwrap();
+        assert!(storage.get("a").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,252 @@
+use rquest_cache::{CacheMiddleware, CacheStatus, CacheStorage, DiskStorage};
+use rquest_middleware::{ClientBuilder, ClientWithMiddleware, Response};
+use rquest_mock::matchers::{header, header_exists, method};
+use rquest_mock::{Mock, MockTransport};
+
+const URL: &str = "http://example.com/data";
+
+fn client<S: CacheStorage>(
+    transport: &MockTransport,
+    cache: CacheMiddleware<S>,
+) -> ClientWithMiddleware {
+    ClientBuilder::new(rquest::Client::new())
+        .with(cache)
+        .with_transport(transport.clone())
+        .build()
+}
+
+async fn get(client: &ClientWithMiddleware) -> (CacheStatus, String) {
+    let res = client.get(URL).send().await.unwrap();
+    let status = *res.extensions().get::<CacheStatus>().unwrap();
+    (status, res.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn serves_fresh_responses_from_the_cache() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET"))
+            .respond_with(
+                Response::builder()
+                    .header("cache-control", "max-age=60")
+                    .body("data")
+                    .unwrap(),
+            )
+            .expect(1),
+    );
+
+    let client = client(&transport, CacheMiddleware::default());
+    assert_eq!(get(&client).await, (CacheStatus::Miss, "data".to_owned()));
+
+    let res = client.get(URL).send().await.unwrap();
+    assert_eq!(res.extensions().get(), Some(&CacheStatus::Hit));
+    assert_eq!(res.headers()["age"], "0");
+    assert_eq!(res.text().await.unwrap(), "data");
+
+    transport.verify();
+}
+
+#[tokio::test]
+async fn revalidates_stale_responses() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(header("if-none-match", "\"v1\""))
+            .named("revalidation")
+            .respond_with(
+                Response::builder()
+                    .status(304)
+                    .header("etag", "\"v1\"")
+                    .header("x-revalidated", "yes")
+                    .body("")
+                    .unwrap(),
+            )
+            .expect(1),
+    );
+    transport.mount(
+        Mock::given(method("GET"))
+            .named("initial")
+            .respond_with(
+                Response::builder()
+                    .header("cache-control", "no-cache")
+                    .header("etag", "\"v1\"")
+                    .body("data")
+                    .unwrap(),
+            )
+            .expect(1),
+    );
+
+    let client = client(&transport, CacheMiddleware::default());
+    assert_eq!(get(&client).await, (CacheStatus::Miss, "data".to_owned()));
+
+    let res = client.get(URL).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.extensions().get(), Some(&CacheStatus::Revalidated));
+    assert_eq!(res.headers()["x-revalidated"], "yes");
+    assert_eq!(res.text().await.unwrap(), "data");
+
+    transport.verify();
+    assert_eq!(
+        transport.call_order(),
+        vec![Some("initial".to_owned()), Some("revalidation".to_owned())]
+    );
+}
+
+#[tokio::test]
+async fn honours_no_store() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET"))
+            .respond_with(
+                Response::builder()
+                    .header("cache-control", "max-age=60, no-store")
+                    .body("data")
+                    .unwrap(),
+            )
+            .expect(2),
+    );
+
+    let client = client(&transport, CacheMiddleware::default());
+    assert_eq!(get(&client).await.0, CacheStatus::Miss);
+    assert_eq!(get(&client).await.0, CacheStatus::Miss);
+
+    transport.verify();
+}
+
+#[tokio::test]
+async fn does_not_store_bodies_over_the_size_limit() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET"))
+            .respond_with(
+                Response::builder()
+                    .header("cache-control", "max-age=60")
+                    .body("data")
+                    .unwrap(),
+            )
+            .expect(2),
+    );
+
+    let client = client(&transport, CacheMiddleware::default().max_body_size(3));
+    assert_eq!(get(&client).await, (CacheStatus::Miss, "data".to_owned()));
+    assert_eq!(get(&client).await, (CacheStatus::Miss, "data".to_owned()));
+
+    transport.verify();
+}
+
+#[tokio::test]
+async fn only_uses_responses_matching_vary() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET"))
+            .respond_with(
+                Response::builder()
+                    .header("cache-control", "max-age=60")
+                    .header("vary", "accept")
+                    .body("data")
+                    .unwrap(),
+            )
+            .expect(3),
+    );
+
+    let client = client(&transport, CacheMiddleware::default());
+    let send = |accept: &'static str| {
+        let client = client.clone();
+        async move {
+            let res = client
+                .get(URL)
+                .header("accept", accept)
+                .send()
+                .await
+                .unwrap();
+            *res.extensions().get::<CacheStatus>().unwrap()
+        }
+    };
+
+    assert_eq!(send("application/json").await, CacheStatus::Miss);
+    assert_eq!(send("application/json").await, CacheStatus::Hit);
+    assert_eq!(send("text/plain").await, CacheStatus::Miss);
+    // Only the last variant is kept.
+    assert_eq!(send("application/json").await, CacheStatus::Miss);
+
+    transport.verify();
+}
+
+#[tokio::test]
+async fn unsafe_methods_invalidate() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("POST"))
+            .respond_with(Response::builder().status(204).body("").unwrap())
+            .expect(1),
+    );
+    transport.mount(
+        Mock::given(method("GET"))
+            .respond_with(
+                Response::builder()
+                    .header("cache-control", "max-age=60")
+                    .body("data")
+                    .unwrap(),
+            )
+            .expect(2),
+    );
+
+    let client = client(&transport, CacheMiddleware::default());
+    assert_eq!(get(&client).await.0, CacheStatus::Miss);
+    assert_eq!(get(&client).await.0, CacheStatus::Hit);
+    client.post(URL).body("update").send().await.unwrap();
+    assert_eq!(get(&client).await.0, CacheStatus::Miss);
+
+    transport.verify();
+}
+
+#[tokio::test]
+async fn only_if_cached_without_entry_is_gateway_timeout() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(header_exists("cache-control"))
+            .respond_with(Response::builder().body("").unwrap())
+            .expect(0),
+    );
+
+    let client = client(&transport, CacheMiddleware::default());
+    let res = client
+        .get(URL)
+        .header("cache-control", "only-if-cached")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 504);
+
+    transport.verify();
+}
+
+#[tokio::test]
+async fn disk_storage_outlives_the_client() {
+    let dir = tempfile::tempdir().unwrap();
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET"))
+            .respond_with(
+                Response::builder()
+                    .header("cache-control", "max-age=60")
+                    .body("data")
+                    .unwrap(),
+            )
+            .expect(1),
+    );
+
+    let first = client(
+        &transport,
+        CacheMiddleware::new(DiskStorage::new(dir.path())),
+    );
+    assert_eq!(get(&first).await, (CacheStatus::Miss, "data".to_owned()));
+    drop(first);
+
+    let second = client(
+        &transport,
+        CacheMiddleware::new(DiskStorage::new(dir.path())),
+    );
+    assert_eq!(get(&second).await, (CacheStatus::Hit, "data".to_owned()));
+
+    transport.verify();
+}
@@ -0,0 +1,319 @@
+//! The state machine of a single circuit.
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use wasmtimer::std::Instant;
+
+use crate::CircuitBreakerError;
+
+/// The state of a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CircuitState {
+    /// Requests go through, failures are counted.
+    Closed,
+    /// Requests fail right away with [`CircuitBreakerError::Open`].
+    Open,
+    /// A few trial requests go through to decide whether to close the circuit again, the others
+    /// fail with [`CircuitBreakerError::HalfOpen`].
+    HalfOpen,
+}
+
+/// How a request admitted by a circuit ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Success,
+    Failure,
+    /// The request was dropped before completing.
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Settings {
+    pub(crate) failure_threshold: u32,
+    pub(crate) success_threshold: u32,
+    pub(crate) half_open_max_calls: u32,
+    pub(crate) open_duration: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            success_threshold: 1,
+            half_open_max_calls: 1,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Option<Instant> },
+    HalfOpen { in_flight: u32, successes: u32 },
+}
+
+/// A change of state, from the first to the second.
+pub(crate) type Transition = (CircuitState, CircuitState);
+
+#[derive(Debug)]
+pub(crate) struct Circuit {
+    state: State,
+    /// Bumped on every transition, so that requests admitted in a previous state don't affect
+    /// the current one.
+    generation: u64,
+}
+
+impl Circuit {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: State::Closed { failures: 0 },
+            generation: 0,
+        }
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        match self.state {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { .. } => CircuitState::Open,
+            State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Returns `true` if the circuit holds no information worth keeping.
+    pub(crate) fn is_idle(&self) -> bool {
+        matches!(self.state, State::Closed { failures: 0 })
+    }
+
+    /// Admits a request at `now`, returning the generation to pass to [`Circuit::record`].
+    pub(crate) fn try_acquire(
+        &mut self,
+        settings: &Settings,
+        now: Instant,
+    ) -> (Result<u64, CircuitBreakerError>, Option<Transition>) {
+        match &mut self.state {
+            State::Closed { .. } => (Ok(self.generation), None),
+            State::Open { until: Some(until) } if now >= *until => {
+                let transition = self.transition(State::HalfOpen {
+                    in_flight: 1,
+                    successes: 0,
+                });
+                (Ok(self.generation), Some(transition))
+            }
+            State::Open { until } => {
+                let retry_after =
+                    until.map_or(Duration::MAX, |until| until.saturating_duration_since(now));
+                (Err(CircuitBreakerError::Open { retry_after }), None)
+            }
+            State::HalfOpen { in_flight, .. } if *in_flight < settings.half_open_max_calls => {
+                *in_flight += 1;
+                (Ok(self.generation), None)
+            }
+            State::HalfOpen { .. } => (Err(CircuitBreakerError::HalfOpen), None),
+        }
+    }
+
+    /// Records the outcome of a request admitted in `generation`.
+    pub(crate) fn record(
+        &mut self,
+        generation: u64,
+        outcome: Outcome,
+        settings: &Settings,
+        now: Instant,
+    ) -> Option<Transition> {
+        if generation != self.generation {
+            return None;
+        }
+
+        // An open duration past the latest `Instant` keeps the circuit open.
+        let open = State::Open {
+            until: now.checked_add(settings.open_duration),
+        };
+        match (&mut self.state, outcome) {
+            (State::Closed { failures }, Outcome::Success) => {
+                *failures = 0;
+                None
+            }
+            (State::Closed { failures }, Outcome::Failure) => {
+                *failures += 1;
+                if *failures >= settings.failure_threshold {
+                    Some(self.transition(open))
+                } else {
+                    None
+                }
+            }
+            (State::HalfOpen { in_flight, .. }, Outcome::Cancelled) => {
+                *in_flight = in_flight.saturating_sub(1);
+                None
+            }
+            (
+                State::HalfOpen {
+                    in_flight,
+                    successes,
+                },
+                Outcome::Success,
+            ) => {
+                // Frees the slot of the trial, so that more trials can run when the success
+                // threshold is above the number of concurrent trials.
+                *in_flight = in_flight.saturating_sub(1);
+                *successes += 1;
+                if *successes >= settings.success_threshold {
+                    Some(self.transition(State::Closed { failures: 0 }))
+                } else {
+                    None
+                }
+            }
+            (State::HalfOpen { .. }, Outcome::Failure) => Some(self.transition(open)),
+            (State::Closed { .. }, Outcome::Cancelled) | (State::Open { .. }, _) => None,
+        }
+    }
+
+    fn transition(&mut self, state: State) -> Transition {
+        let from = self.state();
+        self.state = state;
+        self.generation += 1;
+        (from, self.state())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> Settings {
+        Settings {
+            failure_threshold: 2,
+            success_threshold: 2,
+            half_open_max_calls: 2,
+            open_duration: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let (settings, now) = (settings(), Instant::now());
+        let mut circuit = Circuit::new();
+
+        let generation = circuit.try_acquire(&settings, now).0.unwrap();
+        assert_eq!(
+            circuit.record(generation, Outcome::Failure, &settings, now),
+            None
+        );
+        assert_eq!(
+            circuit.record(generation, Outcome::Success, &settings, now),
+            None
+        );
+        assert_eq!(
+            circuit.record(generation, Outcome::Failure, &settings, now),
+            None
+        );
+        assert_eq!(
+            circuit.record(generation, Outcome::Failure, &settings, now),
+            Some((CircuitState::Closed, CircuitState::Open))
+        );
+
+        let later = now + Duration::from_secs(4);
+        assert!(matches!(
+            circuit.try_acquire(&settings, later).0,
+            Err(CircuitBreakerError::Open { retry_after }) if retry_after == Duration::from_secs(6)
+        ));
+        // Requests admitted before the circuit opened are ignored.
+        assert_eq!(
+            circuit.record(generation, Outcome::Success, &settings, later),
+            None
+        );
+        assert_eq!(circuit.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn stays_open_for_durations_out_of_range() {
+        let settings = Settings {
+            open_duration: Duration::MAX,
+            ..settings()
+        };
+        let now = Instant::now();
+        let mut circuit = Circuit::new();
+        let generation = circuit.try_acquire(&settings, now).0.unwrap();
+        circuit.record(generation, Outcome::Failure, &settings, now);
+        circuit.record(generation, Outcome::Failure, &settings, now);
+
+        let later = now + Duration::from_secs(3600);
+        assert!(matches!(
+            circuit.try_acquire(&settings, later),
+            (Err(CircuitBreakerError::Open { retry_after }), None) if retry_after == Duration::MAX
+        ));
+    }
+
+    #[test]
+    fn half_open_trials_close_or_reopen() {
+        let (settings, now) = (settings(), Instant::now());
+        let mut circuit = Circuit::new();
+        let generation = circuit.try_acquire(&settings, now).0.unwrap();
+        circuit.record(generation, Outcome::Failure, &settings, now);
+        circuit.record(generation, Outcome::Failure, &settings, now);
+
+        let later = now + Duration::from_secs(10);
+        let (first, transition) = circuit.try_acquire(&settings, later);
+        assert_eq!(
+            transition,
+            Some((CircuitState::Open, CircuitState::HalfOpen))
+        );
+        let second = circuit.try_acquire(&settings, later).0.unwrap();
+        assert!(matches!(
+            circuit.try_acquire(&settings, later).0,
+            Err(CircuitBreakerError::HalfOpen)
+        ));
+
+        assert_eq!(
+            circuit.record(second, Outcome::Cancelled, &settings, later),
+            None
+        );
+        let third = circuit.try_acquire(&settings, later).0.unwrap();
+        assert_eq!(
+            circuit.record(third, Outcome::Success, &settings, later),
+            None
+        );
+        assert_eq!(
+            circuit.record(first.unwrap(), Outcome::Success, &settings, later),
+            Some((CircuitState::HalfOpen, CircuitState::Closed))
+        );
+
+        let generation = circuit.try_acquire(&settings, later).0.unwrap();
+        circuit.record(generation, Outcome::Failure, &settings, later);
+        circuit.record(generation, Outcome::Failure, &settings, later);
+        let reopened = later + Duration::from_secs(10);
+        let trial = circuit.try_acquire(&settings, reopened).0.unwrap();
+        assert_eq!(
+            circuit.record(trial, Outcome::Failure, &settings, reopened),
+            Some((CircuitState::HalfOpen, CircuitState::Open))
+        );
+    }
+
+    #[test]
+    fn sequential_trials_reach_a_success_threshold_above_the_concurrent_trials() {
+        let settings = Settings {
+            success_threshold: 2,
+            half_open_max_calls: 1,
+            ..settings()
+        };
+        let now = Instant::now();
+        let mut circuit = Circuit::new();
+        let generation = circuit.try_acquire(&settings, now).0.unwrap();
+        circuit.record(generation, Outcome::Failure, &settings, now);
+        circuit.record(generation, Outcome::Failure, &settings, now);
+
+        let later = now + Duration::from_secs(10);
+        let first = circuit.try_acquire(&settings, later).0.unwrap();
+        assert_eq!(
+            circuit.record(first, Outcome::Success, &settings, later),
+            None
+        );
+        let second = circuit.try_acquire(&settings, later).0.unwrap();
+        assert_eq!(
+            circuit.record(second, Outcome::Success, &settings, later),
+            Some((CircuitState::HalfOpen, CircuitState::Closed))
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::hash::Hash;
+
+use http::Extensions;
+use rquest::Request;
+
+/// Decides which circuit a request goes through, see [`CircuitBreakerMiddleware::keyed`].
+///
+/// Requests for which [`extract`](Self::extract) returns `None` bypass the circuit breaker.
+///
+/// It's implemented for closures returning an `Option` of any hashable key:
+///
+/// ```
+/// use rquest_circuit_breaker::CircuitBreakerMiddleware;
+///
+/// // One circuit per host and first path segment, e.g. per API of a gateway.
+/// let breaker = CircuitBreakerMiddleware::keyed(
+///     |req: &rquest::Request, _extensions: &http::Extensions| {
+///         let url = req.url();
+///         let api = url.path_segments()?.next()?.to_owned();
+///         Some((url.host_str()?.to_owned(), api))
+///     },
+/// );
+/// ```
+///
+/// [`CircuitBreakerMiddleware::keyed`]: crate::CircuitBreakerMiddleware::keyed
+pub trait KeyExtractor: 'static + Send + Sync {
+    type Key: Hash + Eq + Clone + Send + Sync + 'static;
+
+    fn extract(&self, req: &Request, extensions: &Extensions) -> Option<Self::Key>;
+}
+
+impl<F, K> KeyExtractor for F
+where
+    F: Send + Sync + 'static + Fn(&Request, &Extensions) -> Option<K>,
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type Key = K;
+
+    fn extract(&self, req: &Request, extensions: &Extensions) -> Option<K> {
+        (self)(req, extensions)
+    }
+}
+
+/// One circuit per host and port, the default of [`CircuitBreakerMiddleware::new`].
+///
+/// [`CircuitBreakerMiddleware::new`]: crate::CircuitBreakerMiddleware::new
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByHost;
+
+impl KeyExtractor for ByHost {
+    type Key = (String, Option<u16>);
+
+    fn extract(&self, req: &Request, _extensions: &Extensions) -> Option<Self::Key> {
+        let url = req.url();
+        let host = url.host_str()?.to_ascii_lowercase();
+        Some((host, url.port_or_known_default()))
+    }
+}
@@ -0,0 +1,50 @@
+//! Circuit breaker middleware built on [`rquest_middleware`].
+//!
+//! [`CircuitBreakerMiddleware`] tracks failures per host, or per custom key, and fails requests
+//! right away with [`CircuitBreakerError`] while a downstream is considered down, instead of
+//! letting every caller retry and pile up load on it. Failures are classified with the same
+//! [`RetryableStrategy`](rquest_retry::RetryableStrategy) as `rquest-retry`.
+//!
+//! ## Example
+//!
+//! ```
+//! use rquest_circuit_breaker::{CircuitBreakerError, CircuitBreakerMiddleware};
+//! use rquest_middleware::{ClientBuilder, Error};
+//!
+//! async fn run() {
+//!     let client = ClientBuilder::new(rquest::Client::new())
+//!         .with(CircuitBreakerMiddleware::new())
+//!         .build();
+//!
+//!     match client.get("https://truelayer.com").send().await {
+//!         Err(Error::Middleware(err)) if err.is::<CircuitBreakerError>() => {
+//!             println!("TrueLayer is down, not even trying");
+//!         }
+//!         res => println!("{res:?}"),
+//!     }
+//! }
+//! ```
+
+mod circuit;
+mod key;
+mod middleware;
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub use circuit::CircuitState;
+pub use key::{ByHost, KeyExtractor};
+pub use middleware::CircuitBreakerMiddleware;
+
+/// Errors returned by [`CircuitBreakerMiddleware`] instead of sending the request, wrapped in a
+/// [`rquest_middleware::Error::Middleware`].
+#[derive(Debug, Error)]
+pub enum CircuitBreakerError {
+    /// The circuit is open, requests are allowed again after `retry_after`.
+    #[error("Circuit breaker is open, retry after {retry_after:?}")]
+    Open { retry_after: Duration },
+    /// The circuit is half-open and already has as many trial requests in flight as allowed.
+    #[error("Circuit breaker is half-open and waiting for trial requests")]
+    HalfOpen,
+}
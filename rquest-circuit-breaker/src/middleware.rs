@@ -0,0 +1,267 @@
+//! `CircuitBreakerMiddleware` stops sending requests to failing downstreams.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use http::Extensions;
+use rquest::Request;
+use rquest_middleware::{Error, Middleware, Next, Response, Result};
+use rquest_retry::{DefaultRetryableStrategy, Retryable, RetryableStrategy};
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use wasmtimer::std::Instant;
+
+use crate::circuit::{Circuit, CircuitState, Outcome, Settings, Transition};
+use crate::key::{ByHost, KeyExtractor};
+
+/// Idle circuits are dropped once there are this many, so that breaking by e.g. host doesn't
+/// grow without bounds.
+const PRUNE_THRESHOLD: usize = 1024;
+
+type StateChangeCallback<K> = Box<dyn Fn(&K, CircuitState, CircuitState) + Send + Sync>;
+
+/// `CircuitBreakerMiddleware` fails requests right away while their downstream is failing,
+/// instead of piling more load on it.
+///
+/// Each circuit, one per host by default or per key with [`keyed`](Self::keyed), starts
+/// [`Closed`](CircuitState::Closed). After [`failure_threshold`](Self::failure_threshold)
+/// consecutive failures it opens, and requests fail with [`CircuitBreakerError::Open`] for
+/// [`open_duration`](Self::open_duration). It then becomes half-open: up to
+/// [`half_open_max_calls`](Self::half_open_max_calls) trial requests go through, and the circuit
+/// closes after [`success_threshold`](Self::success_threshold) successes or opens again on the
+/// first failure.
+///
+/// Outcomes are classified with a [`RetryableStrategy`] from `rquest-retry`: a
+/// [`Retryable::Transient`] result is a failure, anything else, including fatal errors such as a
+/// `404`, shows the downstream is up and counts as a success. Attach the circuit breaker after
+/// [`RetryTransientMiddleware`](rquest_retry::RetryTransientMiddleware) so that every attempt
+/// goes through it: its errors are fatal for the retry middleware, which stops retrying as soon
+/// as the circuit opens.
+///
+/// ```
+/// use std::time::Duration;
+/// use rquest_circuit_breaker::CircuitBreakerMiddleware;
+/// use rquest_middleware::ClientBuilder;
+/// use rquest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+///
+/// let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+///     .with(
+///         CircuitBreakerMiddleware::new()
+///             .failure_threshold(10)
+///             .open_duration(Duration::from_secs(60))
+///             .on_state_change(|(host, _port), from, to| {
+///                 println!("circuit for {host} went from {from:?} to {to:?}");
+///             }),
+///     )
+///     .build();
+/// ```
+///
+/// [`CircuitBreakerError::Open`]: crate::CircuitBreakerError::Open
+pub struct CircuitBreakerMiddleware<K: KeyExtractor = ByHost, R = DefaultRetryableStrategy> {
+    key: K,
+    retryable_strategy: R,
+    settings: Settings,
+    circuits: Mutex<HashMap<K::Key, Circuit>>,
+    callbacks: Vec<StateChangeCallback<K::Key>>,
+}
+
+impl CircuitBreakerMiddleware {
+    /// A circuit breaker with one circuit per host.
+    pub fn new() -> Self {
+        Self::keyed(ByHost)
+    }
+}
+
+impl Default for CircuitBreakerMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: KeyExtractor> CircuitBreakerMiddleware<K> {
+    /// A circuit breaker with one circuit per key.
+    pub fn keyed(key: K) -> Self {
+        Self {
+            key,
+            retryable_strategy: DefaultRetryableStrategy,
+            settings: Settings::default(),
+            circuits: Mutex::new(HashMap::new()),
+            callbacks: Vec::new(),
+        }
+    }
+}
+
+impl<K, R> CircuitBreakerMiddleware<K, R>
+where
+    K: KeyExtractor,
+    R: RetryableStrategy + Send + Sync + 'static,
+{
+    /// Classify outcomes with `retryable_strategy` instead of [`DefaultRetryableStrategy`].
+    pub fn with_retryable_strategy<S>(self, retryable_strategy: S) -> CircuitBreakerMiddleware<K, S>
+    where
+        S: RetryableStrategy + Send + Sync + 'static,
+    {
+        CircuitBreakerMiddleware {
+            key: self.key,
+            retryable_strategy,
+            settings: self.settings,
+            circuits: self.circuits,
+            callbacks: self.callbacks,
+        }
+    }
+
+    /// The number of consecutive failures opening the circuit, 5 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn failure_threshold(mut self, threshold: u32) -> Self {
+        assert!(threshold > 0, "the failure threshold can't be zero");
+        self.settings.failure_threshold = threshold;
+        self
+    }
+
+    /// How long the circuit stays open before letting trial requests through, 30 seconds by
+    /// default. With [`Duration::MAX`], an open circuit stays open.
+    pub fn open_duration(mut self, duration: Duration) -> Self {
+        self.settings.open_duration = duration;
+        self
+    }
+
+    /// The number of concurrent trial requests while half-open, 1 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `calls` is zero.
+    pub fn half_open_max_calls(mut self, calls: u32) -> Self {
+        assert!(calls > 0, "half-open circuits must allow at least one call");
+        self.settings.half_open_max_calls = calls;
+        self
+    }
+
+    /// The number of successful trial requests closing the circuit, 1 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn success_threshold(mut self, threshold: u32) -> Self {
+        assert!(threshold > 0, "the success threshold can't be zero");
+        self.settings.success_threshold = threshold;
+        self
+    }
+
+    /// Calls `callback` with the key of the circuit and its previous and new state whenever a
+    /// circuit changes state. Callbacks run in the order they were added.
+    pub fn on_state_change<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&K::Key, CircuitState, CircuitState) + Send + Sync + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+}
+
+impl<K: KeyExtractor, R> CircuitBreakerMiddleware<K, R> {
+    /// The current state of the circuit for `key`.
+    ///
+    /// An open circuit only becomes half-open when a request tries to go through.
+    pub fn state(&self, key: &K::Key) -> CircuitState {
+        self.circuits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .map_or(CircuitState::Closed, Circuit::state)
+    }
+
+    fn record(&self, key: &K::Key, generation: u64, outcome: Outcome) {
+        let transition = self
+            .circuits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(key)
+            .and_then(|circuit| {
+                circuit.record(generation, outcome, &self.settings, Instant::now())
+            });
+        self.notify(key, transition);
+    }
+
+    fn notify(&self, key: &K::Key, transition: Option<Transition>) {
+        if let Some((from, to)) = transition {
+            for callback in &self.callbacks {
+                callback(key, from, to);
+            }
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<K, R> Middleware for CircuitBreakerMiddleware<K, R>
+where
+    K: KeyExtractor,
+    R: RetryableStrategy + Send + Sync + 'static,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let key = match self.key.extract(&req, extensions) {
+            Some(key) => key,
+            None => return next.run(req, extensions).await,
+        };
+
+        let (admitted, transition) = {
+            let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+            if circuits.len() >= PRUNE_THRESHOLD && !circuits.contains_key(&key) {
+                circuits.retain(|_, circuit| !circuit.is_idle());
+            }
+            circuits
+                .entry(key.clone())
+                .or_insert_with(Circuit::new)
+                .try_acquire(&self.settings, Instant::now())
+        };
+        self.notify(&key, transition);
+        let generation = admitted.map_err(Error::middleware)?;
+
+        let mut permit = Permit {
+            middleware: self,
+            key: Some(key),
+            generation,
+        };
+        let res = next.run(req, extensions).await;
+        let outcome = match self.retryable_strategy.handle(&res) {
+            Some(Retryable::Transient) => Outcome::Failure,
+            _ => Outcome::Success,
+        };
+        permit.complete(outcome);
+        res
+    }
+}
+
+/// Records the outcome of an admitted request, or its cancellation if the request future is
+/// dropped, so that half-open circuits don't wait for trials which will never complete.
+struct Permit<'a, K: KeyExtractor, R> {
+    middleware: &'a CircuitBreakerMiddleware<K, R>,
+    key: Option<K::Key>,
+    generation: u64,
+}
+
+impl<K: KeyExtractor, R> Permit<'_, K, R> {
+    fn complete(&mut self, outcome: Outcome) {
+        if let Some(key) = self.key.take() {
+            self.middleware.record(&key, self.generation, outcome);
+        }
+    }
+}
+
+impl<K: KeyExtractor, R> Drop for Permit<'_, K, R> {
+    fn drop(&mut self) {
+        self.complete(Outcome::Cancelled);
+    }
+}
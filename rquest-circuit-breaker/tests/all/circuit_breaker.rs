@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rquest_circuit_breaker::{CircuitBreakerError, CircuitBreakerMiddleware, CircuitState};
+use rquest_middleware::{ClientBuilder, ClientWithMiddleware, Error, Middleware, Response};
+use rquest_mock::matchers::{header, method};
+use rquest_mock::{Mock, MockTransport, RecordedRequest};
+
+fn client<M: Middleware>(transport: &MockTransport, breaker: M) -> ClientWithMiddleware {
+    ClientBuilder::new(rquest::Client::new())
+        .with(breaker)
+        .with_transport(transport.clone())
+        .build()
+}
+
+/// A transport answering every request with the status currently in `status`.
+fn transport(status: &Arc<AtomicU16>) -> MockTransport {
+    let status = status.clone();
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET")).respond_with(move |_: &RecordedRequest| {
+            Response::builder()
+                .status(status.load(Ordering::SeqCst))
+                .body("")
+        }),
+    );
+    transport
+}
+
+fn breaker_error(err: Error) -> CircuitBreakerError {
+    match err {
+        Error::Middleware(err) => err.downcast().unwrap(),
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn opens_then_recovers() {
+    let transitions = Arc::new(Mutex::new(Vec::new()));
+    let recorded = transitions.clone();
+    let status = Arc::new(AtomicU16::new(503));
+    let transport = transport(&status);
+    let client = client(
+        &transport,
+        CircuitBreakerMiddleware::new()
+            .failure_threshold(2)
+            .open_duration(Duration::from_secs(10))
+            .on_state_change(move |(host, _), from, to| {
+                recorded.lock().unwrap().push((host.clone(), from, to));
+            }),
+    );
+
+    for _ in 0..2 {
+        let res = client.get("http://a.com/").send().await.unwrap();
+        assert_eq!(res.status(), 503);
+    }
+    let err = client.get("http://a.com/").send().await.unwrap_err();
+    assert!(matches!(
+        breaker_error(err),
+        CircuitBreakerError::Open { retry_after } if retry_after == Duration::from_secs(10)
+    ));
+    assert_eq!(transport.received_requests().len(), 2);
+
+    // Other hosts have their own circuit.
+    let res = client.get("http://b.com/").send().await.unwrap();
+    assert_eq!(res.status(), 503);
+
+    tokio::time::advance(Duration::from_secs(10)).await;
+    status.store(200, Ordering::SeqCst);
+    let res = client.get("http://a.com/").send().await.unwrap();
+    assert_eq!(res.status(), 200);
+
+    assert_eq!(
+        *transitions.lock().unwrap(),
+        vec![
+            ("a.com".to_owned(), CircuitState::Closed, CircuitState::Open),
+            (
+                "a.com".to_owned(),
+                CircuitState::Open,
+                CircuitState::HalfOpen
+            ),
+            (
+                "a.com".to_owned(),
+                CircuitState::HalfOpen,
+                CircuitState::Closed
+            ),
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn failed_trial_reopens() {
+    let transport = transport(&Arc::new(AtomicU16::new(500)));
+    // A single circuit for every request.
+    let breaker =
+        CircuitBreakerMiddleware::keyed(|_: &rquest::Request, _: &http::Extensions| Some(()))
+            .failure_threshold(1)
+            .open_duration(Duration::from_secs(10));
+    let client = client(&transport, breaker);
+
+    client.get("http://a.com/").send().await.unwrap();
+    tokio::time::advance(Duration::from_secs(10)).await;
+    client.get("http://b.com/").send().await.unwrap();
+
+    let err = client.get("http://a.com/").send().await.unwrap_err();
+    assert!(matches!(
+        breaker_error(err),
+        CircuitBreakerError::Open { .. }
+    ));
+}
+
+#[tokio::test]
+async fn client_errors_keep_the_circuit_closed() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(header("x-missing", "yes"))
+            .respond_with(Response::builder().status(404).body("").unwrap()),
+    );
+    let breaker = Arc::new(CircuitBreakerMiddleware::new().failure_threshold(1));
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with_arc(breaker.clone())
+        .with_transport(transport.clone())
+        .build();
+
+    for _ in 0..3 {
+        let res = client
+            .get("http://a.com/")
+            .header("x-missing", "yes")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 404);
+    }
+    assert_eq!(
+        breaker.state(&("a.com".to_owned(), Some(80))),
+        CircuitState::Closed
+    );
+}
@@ -0,0 +1 @@
+mod circuit_breaker;
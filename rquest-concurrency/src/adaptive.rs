@@ -0,0 +1,262 @@
+//! `AdaptiveConcurrencyMiddleware` adjusts the number of requests in flight to the downstream.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use http::Extensions;
+use rquest::Request;
+use rquest_middleware::{Error, Middleware, Next, Response, Result};
+use rquest_retry::{default_on_request_failure, default_on_request_success, Retryable};
+use tokio::sync::Notify;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::{timeout, Instant};
+#[cfg(target_arch = "wasm32")]
+use wasmtimer::{std::Instant, tokio::timeout};
+
+use crate::algorithm::{Aimd, LimitAlgorithm, Outcome, Sample};
+use crate::key::{ByHost, Global, KeyExtractor};
+use crate::ConcurrencyLimitError;
+
+/// Unused limiters are dropped once there are this many, so that limiting by e.g. host doesn't
+/// grow without bounds.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// `AdaptiveConcurrencyMiddleware` caps the number of requests in flight like
+/// [`ConcurrencyLimitMiddleware`](crate::ConcurrencyLimitMiddleware), but finds the limit on its
+/// own from the latency and errors of the downstream, instead of relying on a static value tuned
+/// for one environment.
+///
+/// The limit is computed by a [`LimitAlgorithm`], [`Aimd`] or [`Gradient`](crate::Gradient),
+/// separately for each host with [`new`](Self::new), for all requests with
+/// [`global`](Self::global), or for any custom key with [`keyed`](Self::keyed).
+///
+/// Results are classified with `rquest-retry`'s [`default_on_request_success`] and
+/// [`default_on_request_failure`]: transient errors, such as a `503`, a `429` or a timeout, show
+/// the downstream is overloaded and lower the limit. Any other response, including client
+/// errors, is a success. Other errors and cancelled requests don't affect the limit.
+///
+/// Requests over the limit wait until a request completes or the limit grows, for at most
+/// [`queue_timeout`](Self::queue_timeout).
+///
+/// ```
+/// use std::time::Duration;
+/// use rquest_concurrency::{AdaptiveConcurrencyMiddleware, Aimd};
+/// use rquest_middleware::ClientBuilder;
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(
+///         AdaptiveConcurrencyMiddleware::new(
+///             Aimd::new()
+///                 .max_limit(100)
+///                 .latency_threshold(Duration::from_secs(2)),
+///         )
+///         .queue_timeout(Duration::from_secs(5)),
+///     )
+///     .build();
+/// ```
+pub struct AdaptiveConcurrencyMiddleware<A: LimitAlgorithm = Aimd, K: KeyExtractor = ByHost> {
+    algorithm: A,
+    key: K,
+    queue_timeout: Option<Duration>,
+    limiters: Mutex<HashMap<K::Key, Arc<Limiter<A>>>>,
+}
+
+impl<A: LimitAlgorithm> AdaptiveConcurrencyMiddleware<A, ByHost> {
+    /// Adapts the limit of each host with `algorithm`.
+    pub fn new(algorithm: A) -> Self {
+        Self::keyed(algorithm, ByHost)
+    }
+}
+
+impl<A: LimitAlgorithm> AdaptiveConcurrencyMiddleware<A, Global> {
+    /// Adapts a single limit shared by every request with `algorithm`.
+    pub fn global(algorithm: A) -> Self {
+        Self::keyed(algorithm, Global)
+    }
+}
+
+impl<A: LimitAlgorithm, K: KeyExtractor> AdaptiveConcurrencyMiddleware<A, K> {
+    /// Adapts the limit of each key with `algorithm`.
+    pub fn keyed(algorithm: A, key: K) -> Self {
+        Self {
+            algorithm,
+            key,
+            queue_timeout: None,
+            limiters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fails requests with [`ConcurrencyLimitError::QueueTimeout`] when they waited `timeout`
+    /// without being let through. Use [`Duration::ZERO`] to never wait. Requests wait as long as
+    /// needed by default.
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// The current limit for `key`.
+    pub fn limit(&self, key: &K::Key) -> usize {
+        let limiters = self.limiters.lock().unwrap_or_else(|e| e.into_inner());
+        match limiters.get(key) {
+            Some(limiter) => limiter.state().limit,
+            None => self.algorithm.initial().max(1),
+        }
+    }
+
+    fn limiter(&self, key: K::Key) -> Arc<Limiter<A>> {
+        let mut limiters = self.limiters.lock().unwrap_or_else(|e| e.into_inner());
+        if limiters.len() >= PRUNE_THRESHOLD && !limiters.contains_key(&key) {
+            // Nobody else holds a reference to an unused limiter.
+            limiters.retain(|_, limiter| Arc::strong_count(limiter) > 1);
+        }
+        limiters
+            .entry(key)
+            .or_insert_with(|| Arc::new(Limiter::new(self.algorithm.clone())))
+            .clone()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<A: LimitAlgorithm, K: KeyExtractor> Middleware for AdaptiveConcurrencyMiddleware<A, K> {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let limiter = match self.key.extract(&req, extensions) {
+            Some(key) => self.limiter(key),
+            None => return next.run(req, extensions).await,
+        };
+
+        let in_flight = match limiter.try_acquire() {
+            Some(in_flight) => in_flight,
+            None => {
+                let acquire = limiter.acquire();
+                match self.queue_timeout {
+                    Some(queue_timeout) => timeout(queue_timeout, acquire).await.map_err(|_| {
+                        Error::middleware(ConcurrencyLimitError::QueueTimeout {
+                            timeout: queue_timeout,
+                        })
+                    })?,
+                    None => acquire.await,
+                }
+            }
+        };
+
+        let mut permit = Permit {
+            limiter: Some(limiter),
+            in_flight,
+            start: Instant::now(),
+        };
+        let res = next.run(req, extensions).await;
+        let outcome = match &res {
+            Ok(res) => match default_on_request_success(res) {
+                Some(Retryable::Transient) => Some(Outcome::Dropped),
+                _ => Some(Outcome::Success),
+            },
+            // Errors such as an invalid request say nothing about the downstream.
+            Err(err) => match default_on_request_failure(err) {
+                Some(Retryable::Transient) => Some(Outcome::Dropped),
+                _ => None,
+            },
+        };
+        permit.complete(outcome);
+        res
+    }
+}
+
+/// The limit of a single key.
+struct Limiter<A> {
+    state: Mutex<LimiterState<A>>,
+    /// Notified whenever a request completes, letting a queued request through if the limit
+    /// allows it.
+    released: Notify,
+}
+
+struct LimiterState<A> {
+    algorithm: A,
+    limit: usize,
+    in_flight: usize,
+}
+
+impl<A: LimitAlgorithm> Limiter<A> {
+    fn new(algorithm: A) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                limit: algorithm.initial().max(1),
+                algorithm,
+                in_flight: 0,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, LimiterState<A>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Lets a request through if the limit allows it, returning the number of requests in
+    /// flight.
+    fn try_acquire(&self) -> Option<usize> {
+        let mut state = self.state();
+        if state.in_flight < state.limit {
+            state.in_flight += 1;
+            Some(state.in_flight)
+        } else {
+            None
+        }
+    }
+
+    async fn acquire(&self) -> usize {
+        loop {
+            // Registered before checking the limit, so that a release in between isn't missed.
+            let released = self.released.notified();
+            if let Some(in_flight) = self.try_acquire() {
+                return in_flight;
+            }
+            released.await;
+        }
+    }
+
+    fn release(&self, sample: Option<Sample>) {
+        {
+            let mut state = self.state();
+            state.in_flight -= 1;
+            if let Some(sample) = sample {
+                let limit = state.limit;
+                state.limit = state.algorithm.update(limit, &sample).max(1);
+            }
+        }
+        // The limit may have grown by more than one, every queued request checks it again.
+        self.released.notify_waiters();
+    }
+}
+
+/// A request let through by a [`Limiter`], released once it completes or is dropped.
+struct Permit<A: LimitAlgorithm> {
+    limiter: Option<Arc<Limiter<A>>>,
+    in_flight: usize,
+    start: Instant,
+}
+
+impl<A: LimitAlgorithm> Permit<A> {
+    fn complete(&mut self, outcome: Option<Outcome>) {
+        if let Some(limiter) = self.limiter.take() {
+            let sample = outcome.map(|outcome| Sample {
+                latency: self.start.elapsed(),
+                in_flight: self.in_flight,
+                outcome,
+            });
+            limiter.release(sample);
+        }
+    }
+}
+
+impl<A: LimitAlgorithm> Drop for Permit<A> {
+    fn drop(&mut self) {
+        self.complete(None);
+    }
+}
@@ -0,0 +1,302 @@
+//! Algorithms adjusting the limit of [`AdaptiveConcurrencyMiddleware`].
+//!
+//! [`AdaptiveConcurrencyMiddleware`]: crate::AdaptiveConcurrencyMiddleware
+use std::time::Duration;
+
+/// How a request observed by an adaptive limit ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The downstream answered, even with a client error.
+    Success,
+    /// The request failed in a way showing the downstream is overloaded, e.g. a `503` or a
+    /// timeout.
+    Dropped,
+}
+
+/// A request observed by an adaptive limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// The time between sending the request and receiving the response headers.
+    pub latency: Duration,
+    /// The number of requests in flight when the request was sent, itself included.
+    pub in_flight: usize,
+    /// How the request ended.
+    pub outcome: Outcome,
+}
+
+/// Computes the limit of [`AdaptiveConcurrencyMiddleware`] from the requests it observes.
+///
+/// Each key gets its own clone of the algorithm, so implementations can keep per-key state in
+/// `self`.
+///
+/// [`AdaptiveConcurrencyMiddleware`]: crate::AdaptiveConcurrencyMiddleware
+pub trait LimitAlgorithm: Clone + Send + Sync + 'static {
+    /// The limit of a key before any request completed.
+    fn initial(&self) -> usize;
+
+    /// Returns the new limit after `sample` completed while the limit was `limit`. The limit is
+    /// never lowered below 1.
+    fn update(&mut self, limit: usize, sample: &Sample) -> usize;
+}
+
+/// Additive increase, multiplicative decrease: the limit grows by one after each success while
+/// it is in use, and shrinks by [`backoff_ratio`](Self::backoff_ratio) after each drop.
+///
+/// Optionally, requests slower than [`latency_threshold`](Self::latency_threshold) count as
+/// drops.
+#[derive(Debug, Clone)]
+pub struct Aimd {
+    initial_limit: usize,
+    min_limit: usize,
+    max_limit: usize,
+    backoff_ratio: f64,
+    latency_threshold: Option<Duration>,
+}
+
+impl Aimd {
+    /// Starts at 20 requests, between 1 and 200, backing off by 10% on drops.
+    pub fn new() -> Self {
+        Self {
+            initial_limit: 20,
+            min_limit: 1,
+            max_limit: 200,
+            backoff_ratio: 0.9,
+            latency_threshold: None,
+        }
+    }
+
+    pub fn initial_limit(mut self, limit: usize) -> Self {
+        self.initial_limit = limit;
+        self
+    }
+
+    pub fn min_limit(mut self, limit: usize) -> Self {
+        self.min_limit = limit;
+        self
+    }
+
+    pub fn max_limit(mut self, limit: usize) -> Self {
+        self.max_limit = limit;
+        self
+    }
+
+    /// The factor applied to the limit on drops.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` is not between 0 and 1, both excluded.
+    pub fn backoff_ratio(mut self, ratio: f64) -> Self {
+        assert!(
+            ratio > 0.0 && ratio < 1.0,
+            "the backoff ratio must be between 0 and 1"
+        );
+        self.backoff_ratio = ratio;
+        self
+    }
+
+    /// Requests slower than `threshold` count as drops.
+    pub fn latency_threshold(mut self, threshold: Duration) -> Self {
+        self.latency_threshold = Some(threshold);
+        self
+    }
+}
+
+impl Default for Aimd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LimitAlgorithm for Aimd {
+    fn initial(&self) -> usize {
+        self.initial_limit
+    }
+
+    fn update(&mut self, limit: usize, sample: &Sample) -> usize {
+        let too_slow = self
+            .latency_threshold
+            .is_some_and(|threshold| sample.latency > threshold);
+        let limit = if sample.outcome == Outcome::Dropped || too_slow {
+            (limit as f64 * self.backoff_ratio) as usize
+        } else if sample.in_flight * 2 >= limit {
+            limit + 1
+        } else {
+            // Most of the limit is unused, succeeding says nothing about a higher one.
+            limit
+        };
+        limit.max(self.min_limit).min(self.max_limit)
+    }
+}
+
+/// A gradient algorithm in the spirit of TCP Vegas: the limit follows the ratio between the
+/// usual latency and the latency of the latest request, shrinking as queues build up on the
+/// downstream and growing back when latency returns to normal.
+///
+/// The usual latency is an exponential moving average over
+/// [`latency_window`](Self::latency_window) requests. Drops shrink the limit by half.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    initial_limit: usize,
+    min_limit: usize,
+    max_limit: usize,
+    smoothing: f64,
+    tolerance: f64,
+    latency_window: u32,
+    /// The limit before rounding, so that small gradients still add up.
+    estimate: Option<f64>,
+    /// The moving average of the latency, in seconds.
+    long_latency: Option<f64>,
+}
+
+impl Gradient {
+    /// Starts at 20 requests, between 1 and 200, tolerating latencies up to 1.5 times the usual.
+    pub fn new() -> Self {
+        Self {
+            initial_limit: 20,
+            min_limit: 1,
+            max_limit: 200,
+            smoothing: 0.2,
+            tolerance: 1.5,
+            latency_window: 100,
+            estimate: None,
+            long_latency: None,
+        }
+    }
+
+    pub fn initial_limit(mut self, limit: usize) -> Self {
+        self.initial_limit = limit;
+        self
+    }
+
+    pub fn min_limit(mut self, limit: usize) -> Self {
+        self.min_limit = limit;
+        self
+    }
+
+    pub fn max_limit(mut self, limit: usize) -> Self {
+        self.max_limit = limit;
+        self
+    }
+
+    /// How fast the limit moves towards its new value, from 0 (never) to 1 (right away).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `smoothing` is not between 0 excluded and 1 included.
+    pub fn smoothing(mut self, smoothing: f64) -> Self {
+        assert!(
+            smoothing > 0.0 && smoothing <= 1.0,
+            "the smoothing must be between 0 and 1"
+        );
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// How many times slower than usual a request can be before the limit shrinks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is lower than 1.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        assert!(tolerance >= 1.0, "the tolerance can't be lower than 1");
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// The number of requests the usual latency is averaged over.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is zero.
+    pub fn latency_window(mut self, requests: u32) -> Self {
+        assert!(requests > 0, "the latency window can't be empty");
+        self.latency_window = requests;
+        self
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LimitAlgorithm for Gradient {
+    fn initial(&self) -> usize {
+        self.initial_limit
+    }
+
+    fn update(&mut self, limit: usize, sample: &Sample) -> usize {
+        let estimate = self.estimate.unwrap_or(limit as f64);
+        let new_estimate = if sample.outcome == Outcome::Dropped {
+            estimate / 2.0
+        } else {
+            let latency = sample.latency.as_secs_f64().max(f64::EPSILON);
+            let alpha = 2.0 / (f64::from(self.latency_window) + 1.0);
+            let long_latency = match self.long_latency {
+                Some(long_latency) => long_latency + alpha * (latency - long_latency),
+                None => latency,
+            };
+            self.long_latency = Some(long_latency);
+
+            if (sample.in_flight as f64) < estimate / 2.0 {
+                // Most of the limit is unused, the latency says nothing about a higher one.
+                estimate
+            } else {
+                let gradient = (self.tolerance * long_latency / latency).clamp(0.5, 1.0);
+                let queue = estimate.sqrt();
+                let target = estimate * gradient + queue;
+                estimate + self.smoothing * (target - estimate)
+            }
+        };
+
+        let new_estimate = new_estimate
+            .max(self.min_limit as f64)
+            .min(self.max_limit as f64);
+        self.estimate = Some(new_estimate);
+        new_estimate as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(latency_ms: u64, in_flight: usize, outcome: Outcome) -> Sample {
+        Sample {
+            latency: Duration::from_millis(latency_ms),
+            in_flight,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn aimd_increases_additively_and_decreases_multiplicatively() {
+        let mut aimd = Aimd::new()
+            .max_limit(11)
+            .latency_threshold(Duration::from_secs(1));
+
+        assert_eq!(aimd.update(10, &sample(10, 5, Outcome::Success)), 11);
+        assert_eq!(aimd.update(11, &sample(10, 11, Outcome::Success)), 11);
+        assert_eq!(aimd.update(10, &sample(10, 2, Outcome::Success)), 10);
+        assert_eq!(aimd.update(10, &sample(10, 10, Outcome::Dropped)), 9);
+        assert_eq!(aimd.update(10, &sample(2000, 10, Outcome::Success)), 9);
+        assert_eq!(aimd.update(1, &sample(10, 1, Outcome::Dropped)), 1);
+    }
+
+    #[test]
+    fn gradient_follows_latency() {
+        let mut gradient = Gradient::new().smoothing(1.0).tolerance(1.0);
+
+        // Stable latency: the limit grows by the queue allowance.
+        assert_eq!(gradient.update(16, &sample(100, 16, Outcome::Success)), 20);
+
+        // Twice the usual latency halves the limit, before the queue allowance.
+        let limit = gradient.update(20, &sample(200, 20, Outcome::Success));
+        assert!(limit < 20, "{limit}");
+
+        let before = gradient.estimate.unwrap();
+        gradient.update(limit, &sample(100, limit, Outcome::Dropped));
+        assert_eq!(gradient.estimate, Some(before / 2.0));
+    }
+}
@@ -0,0 +1,197 @@
+//! `ConcurrencyLimitMiddleware` caps the number of requests in flight.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::Extensions;
+use rquest::Request;
+use rquest_middleware::{Error, Middleware, Next, Response, Result};
+use tokio::sync::Semaphore;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::timeout;
+#[cfg(target_arch = "wasm32")]
+use wasmtimer::tokio::timeout;
+
+use crate::key::{ByHost, Global, KeyExtractor};
+use crate::ConcurrencyLimitError;
+
+/// Unused bulkheads are dropped once there are this many, so that limiting by e.g. host doesn't
+/// grow without bounds.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// `ConcurrencyLimitMiddleware` is a bulkhead: it caps the number of requests in flight so that
+/// one slow downstream can't hold on to every task of the application.
+///
+/// Requests are limited per host with [`new`](Self::new), all together with
+/// [`global`](Self::global), or per any custom key with [`keyed`](Self::keyed). Requests over
+/// the limit wait in a queue, first come first served. The queue can be bounded with
+/// [`max_queue`](Self::max_queue) and the wait with [`queue_timeout`](Self::queue_timeout),
+/// requests then fail with a [`ConcurrencyLimitError`].
+///
+/// A request stays in flight until the response headers are received, reading the body is not
+/// limited.
+///
+/// ```
+/// use std::time::Duration;
+/// use rquest_concurrency::ConcurrencyLimitMiddleware;
+/// use rquest_middleware::ClientBuilder;
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(
+///         ConcurrencyLimitMiddleware::new(20)
+///             .max_queue(100)
+///             .queue_timeout(Duration::from_secs(5)),
+///     )
+///     .build();
+/// ```
+pub struct ConcurrencyLimitMiddleware<K: KeyExtractor = ByHost> {
+    key: K,
+    max_concurrency: usize,
+    max_queue: Option<usize>,
+    queue_timeout: Option<Duration>,
+    bulkheads: Mutex<HashMap<K::Key, Arc<Bulkhead>>>,
+}
+
+impl ConcurrencyLimitMiddleware<ByHost> {
+    /// Allows `max_concurrency` requests in flight to each host.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrency` is zero.
+    pub fn new(max_concurrency: usize) -> Self {
+        Self::keyed(max_concurrency, ByHost)
+    }
+}
+
+impl ConcurrencyLimitMiddleware<Global> {
+    /// Allows `max_concurrency` requests in flight in total.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrency` is zero.
+    pub fn global(max_concurrency: usize) -> Self {
+        Self::keyed(max_concurrency, Global)
+    }
+}
+
+impl<K: KeyExtractor> ConcurrencyLimitMiddleware<K> {
+    /// Allows `max_concurrency` requests in flight for each key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrency` is zero.
+    pub fn keyed(max_concurrency: usize, key: K) -> Self {
+        assert!(max_concurrency > 0, "the concurrency limit can't be zero");
+        Self {
+            key,
+            max_concurrency,
+            max_queue: None,
+            queue_timeout: None,
+            bulkheads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fails requests right away with [`ConcurrencyLimitError::QueueFull`] when `max_queue`
+    /// requests for the same key are already waiting. Use `0` to never wait. The queue is
+    /// unbounded by default.
+    pub fn max_queue(mut self, max_queue: usize) -> Self {
+        self.max_queue = Some(max_queue);
+        self
+    }
+
+    /// Fails requests with [`ConcurrencyLimitError::QueueTimeout`] when they waited `timeout`
+    /// without being let through. Requests wait as long as needed by default.
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    fn bulkhead(&self, key: K::Key) -> Arc<Bulkhead> {
+        let mut bulkheads = self.bulkheads.lock().unwrap_or_else(|e| e.into_inner());
+        if bulkheads.len() >= PRUNE_THRESHOLD && !bulkheads.contains_key(&key) {
+            // Nobody else holds a reference to an unused bulkhead.
+            bulkheads.retain(|_, bulkhead| Arc::strong_count(bulkhead) > 1);
+        }
+        bulkheads
+            .entry(key)
+            .or_insert_with(|| Arc::new(Bulkhead::new(self.max_concurrency)))
+            .clone()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<K: KeyExtractor> Middleware for ConcurrencyLimitMiddleware<K> {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let bulkhead = match self.key.extract(&req, extensions) {
+            Some(key) => self.bulkhead(key),
+            None => return next.run(req, extensions).await,
+        };
+
+        let _permit = match bulkhead.semaphore.try_acquire() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let _queued = bulkhead
+                    .enqueue(self.max_queue)
+                    .ok_or_else(|| Error::middleware(ConcurrencyLimitError::QueueFull))?;
+                let acquire = bulkhead.semaphore.acquire();
+                let permit = match self.queue_timeout {
+                    Some(queue_timeout) => timeout(queue_timeout, acquire).await.map_err(|_| {
+                        Error::middleware(ConcurrencyLimitError::QueueTimeout {
+                            timeout: queue_timeout,
+                        })
+                    })?,
+                    None => acquire.await,
+                };
+                permit.expect("the semaphore is never closed")
+            }
+        };
+        next.run(req, extensions).await
+    }
+}
+
+/// The limit of a single key.
+struct Bulkhead {
+    semaphore: Semaphore,
+    queued: AtomicUsize,
+}
+
+impl Bulkhead {
+    fn new(max_concurrency: usize) -> Self {
+        Self {
+            semaphore: Semaphore::new(max_concurrency),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Takes a place in the queue, unless it already holds `max_queue` requests.
+    fn enqueue(&self, max_queue: Option<usize>) -> Option<Queued<'_>> {
+        self.queued
+            .fetch_update(
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+                |queued| match max_queue {
+                    Some(max_queue) if queued >= max_queue => None,
+                    _ => Some(queued + 1),
+                },
+            )
+            .ok()?;
+        Some(Queued(&self.queued))
+    }
+}
+
+/// A place in the queue of a [`Bulkhead`], given back on drop.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,74 @@
+use std::hash::Hash;
+
+use http::Extensions;
+use rquest::Request;
+
+/// Decides which limit a request counts against, see [`ConcurrencyLimitMiddleware::keyed`].
+///
+/// Requests for which [`extract`](Self::extract) returns `None` are not limited.
+///
+/// It's implemented for closures returning an `Option` of any hashable key:
+///
+/// ```
+/// use rquest_concurrency::ConcurrencyLimitMiddleware;
+///
+/// #[derive(Clone)]
+/// struct Downstream(&'static str);
+///
+/// // At most 10 requests in flight per downstream, other requests are not limited.
+/// let bulkhead = ConcurrencyLimitMiddleware::keyed(
+///     10,
+///     |_req: &rquest::Request, extensions: &http::Extensions| {
+///         extensions.get::<Downstream>().map(|downstream| downstream.0)
+///     },
+/// );
+/// ```
+///
+/// [`ConcurrencyLimitMiddleware::keyed`]: crate::ConcurrencyLimitMiddleware::keyed
+pub trait KeyExtractor: 'static + Send + Sync {
+    type Key: Hash + Eq + Clone + Send + Sync + 'static;
+
+    fn extract(&self, req: &Request, extensions: &Extensions) -> Option<Self::Key>;
+}
+
+impl<F, K> KeyExtractor for F
+where
+    F: Send + Sync + 'static + Fn(&Request, &Extensions) -> Option<K>,
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type Key = K;
+
+    fn extract(&self, req: &Request, extensions: &Extensions) -> Option<K> {
+        (self)(req, extensions)
+    }
+}
+
+/// One limit per host and port, the default of [`ConcurrencyLimitMiddleware::new`].
+///
+/// [`ConcurrencyLimitMiddleware::new`]: crate::ConcurrencyLimitMiddleware::new
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByHost;
+
+impl KeyExtractor for ByHost {
+    type Key = (String, Option<u16>);
+
+    fn extract(&self, req: &Request, _extensions: &Extensions) -> Option<Self::Key> {
+        let url = req.url();
+        let host = url.host_str()?.to_ascii_lowercase();
+        Some((host, url.port_or_known_default()))
+    }
+}
+
+/// A single limit shared by every request, see [`ConcurrencyLimitMiddleware::global`].
+///
+/// [`ConcurrencyLimitMiddleware::global`]: crate::ConcurrencyLimitMiddleware::global
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Global;
+
+impl KeyExtractor for Global {
+    type Key = ();
+
+    fn extract(&self, _req: &Request, _extensions: &Extensions) -> Option<()> {
+        Some(())
+    }
+}
@@ -0,0 +1,57 @@
+//! Middleware limiting the number of requests in flight, built on [`rquest_middleware`].
+//!
+//! [`ConcurrencyLimitMiddleware`] is a bulkhead capping the requests in flight per host, per
+//! custom key or globally, so that one slow downstream can't exhaust the tasks of the whole
+//! application. Requests over the limit wait in a bounded queue or fail with a
+//! [`ConcurrencyLimitError`].
+//!
+//! [`AdaptiveConcurrencyMiddleware`] finds the limit on its own instead, lowering it when the
+//! downstream slows down or fails with transient errors and raising it back while it keeps up,
+//! using either the [`Aimd`] or the [`Gradient`] [`LimitAlgorithm`].
+//!
+//! ## Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use rquest_concurrency::{ConcurrencyLimitError, ConcurrencyLimitMiddleware};
+//! use rquest_middleware::{ClientBuilder, Error};
+//!
+//! async fn run() {
+//!     let client = ClientBuilder::new(rquest::Client::new())
+//!         .with(ConcurrencyLimitMiddleware::new(10).queue_timeout(Duration::from_secs(1)))
+//!         .build();
+//!
+//!     match client.get("https://truelayer.com").send().await {
+//!         Err(Error::Middleware(err)) if err.is::<ConcurrencyLimitError>() => {
+//!             println!("too many requests to TrueLayer already");
+//!         }
+//!         res => println!("{res:?}"),
+//!     }
+//! }
+//! ```
+
+mod adaptive;
+mod algorithm;
+mod bulkhead;
+mod key;
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub use adaptive::AdaptiveConcurrencyMiddleware;
+pub use algorithm::{Aimd, Gradient, LimitAlgorithm, Outcome, Sample};
+pub use bulkhead::ConcurrencyLimitMiddleware;
+pub use key::{ByHost, Global, KeyExtractor};
+
+/// Errors returned by the middleware of this crate instead of sending the request, wrapped in a
+/// [`rquest_middleware::Error::Middleware`].
+#[derive(Debug, Error)]
+pub enum ConcurrencyLimitError {
+    /// The concurrency limit was reached and the queue already held as many requests as allowed.
+    #[error("Concurrency limit reached and the queue is full")]
+    QueueFull,
+    /// The request waited in the queue for `timeout` without being let through.
+    #[error("Concurrency limit still reached after waiting for {timeout:?}")]
+    QueueTimeout { timeout: Duration },
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::join_all;
+use rquest_concurrency::{AdaptiveConcurrencyMiddleware, Aimd, ConcurrencyLimitError, Global};
+use rquest_middleware::{from_fn, ClientBuilder, ClientWithMiddleware, Error, FnNext, Response};
+use rquest_mock::matchers::method;
+use rquest_mock::{Mock, MockTransport, RecordedRequest};
+
+type Limit = Arc<AdaptiveConcurrencyMiddleware<Aimd, Global>>;
+
+/// A client answering every request with the status currently in `status`, after `delay`.
+fn client(limit: &Limit, status: &Arc<AtomicU16>, delay: Duration) -> ClientWithMiddleware {
+    let status = status.clone();
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET")).respond_with(move |_: &RecordedRequest| {
+            Response::builder()
+                .status(status.load(Ordering::SeqCst))
+                .body("")
+        }),
+    );
+
+    ClientBuilder::new(rquest::Client::new())
+        .with_arc(limit.clone())
+        .with(from_fn(move |req, next: FnNext| async move {
+            tokio::time::sleep(delay).await;
+            next.run(req).await
+        }))
+        .with_transport(transport)
+        .build()
+}
+
+fn limit_error(err: Error) -> ConcurrencyLimitError {
+    match err {
+        Error::Middleware(err) => err.downcast().unwrap(),
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+#[tokio::test]
+async fn backs_off_on_transient_errors_and_grows_back() {
+    let limit = Arc::new(AdaptiveConcurrencyMiddleware::global(
+        Aimd::new().initial_limit(4).backoff_ratio(0.5),
+    ));
+    let status = Arc::new(AtomicU16::new(503));
+    let client = client(&limit, &status, Duration::ZERO);
+
+    let mut limits = Vec::new();
+    for _ in 0..2 {
+        client.get("http://a.com/").send().await.unwrap();
+        limits.push(limit.limit(&()));
+    }
+    // Client errors show the downstream is up.
+    status.store(404, Ordering::SeqCst);
+    for _ in 0..3 {
+        client.get("http://a.com/").send().await.unwrap();
+        limits.push(limit.limit(&()));
+    }
+
+    // A single request in flight doesn't grow the limit past twice its size.
+    assert_eq!(limits, [2, 1, 2, 3, 3]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_responses_lower_the_limit() {
+    let limit = Arc::new(AdaptiveConcurrencyMiddleware::global(
+        Aimd::new()
+            .initial_limit(2)
+            .backoff_ratio(0.5)
+            .latency_threshold(Duration::from_millis(500)),
+    ));
+    let status = Arc::new(AtomicU16::new(200));
+    let client = client(&limit, &status, Duration::from_secs(1));
+
+    let start = tokio::time::Instant::now();
+    let requests = (0..4).map(|_| client.get("http://a.com/").send());
+    for res in join_all(requests).await {
+        res.unwrap();
+    }
+
+    // Two requests in the first second, then one at a time.
+    assert_eq!(start.elapsed(), Duration::from_secs(3));
+    assert_eq!(limit.limit(&()), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn times_out_in_the_queue() {
+    let limit = Arc::new(
+        AdaptiveConcurrencyMiddleware::global(Aimd::new().initial_limit(1))
+            .queue_timeout(Duration::ZERO),
+    );
+    let status = Arc::new(AtomicU16::new(200));
+    let client = client(&limit, &status, Duration::from_secs(1));
+
+    let (first, second) = tokio::join!(
+        client.get("http://a.com/").send(),
+        client.get("http://a.com/").send()
+    );
+    first.unwrap();
+    assert!(matches!(
+        limit_error(second.unwrap_err()),
+        ConcurrencyLimitError::QueueTimeout { timeout } if timeout == Duration::ZERO
+    ));
+}
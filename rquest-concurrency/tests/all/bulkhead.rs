@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::join_all;
+use rquest_concurrency::{ConcurrencyLimitError, ConcurrencyLimitMiddleware};
+use rquest_middleware::{
+    from_fn, ClientBuilder, ClientWithMiddleware, Error, FnNext, Middleware, Response,
+};
+use rquest_mock::matchers::method;
+use rquest_mock::{Mock, MockTransport};
+
+/// Counts the requests in flight, each taking a second, and records the peak.
+#[derive(Default)]
+struct InFlight {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+fn client<M: Middleware>(limit: M, in_flight: &Arc<InFlight>) -> ClientWithMiddleware {
+    let transport = MockTransport::new();
+    transport.mount(Mock::given(method("GET")).respond_with(Response::builder().body("").unwrap()));
+
+    let in_flight = in_flight.clone();
+    ClientBuilder::new(rquest::Client::new())
+        .with(limit)
+        .with(from_fn(move |req, next: FnNext| {
+            let in_flight = in_flight.clone();
+            async move {
+                let current = in_flight.current.fetch_add(1, Ordering::SeqCst) + 1;
+                in_flight.peak.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                in_flight.current.fetch_sub(1, Ordering::SeqCst);
+                next.run(req).await
+            }
+        }))
+        .with_transport(transport)
+        .build()
+}
+
+fn limit_error(err: Error) -> ConcurrencyLimitError {
+    match err {
+        Error::Middleware(err) => err.downcast().unwrap(),
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn caps_requests_in_flight_per_host() {
+    let in_flight = Arc::new(InFlight::default());
+    let client = client(ConcurrencyLimitMiddleware::new(2), &in_flight);
+
+    let start = tokio::time::Instant::now();
+    let requests = (0..4).map(|_| client.get("http://a.com/").send());
+    for res in join_all(requests).await {
+        res.unwrap();
+    }
+    assert_eq!(in_flight.peak.load(Ordering::SeqCst), 2);
+    assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+    let hosts = ["http://a.com/", "http://b.com/"];
+    let requests = hosts.iter().map(|url| client.get(*url).send());
+    for res in join_all(requests).await {
+        res.unwrap();
+    }
+    assert_eq!(start.elapsed(), Duration::from_secs(3));
+}
+
+#[tokio::test(start_paused = true)]
+async fn rejects_when_the_queue_is_full() {
+    let in_flight = Arc::new(InFlight::default());
+    let client = client(
+        ConcurrencyLimitMiddleware::global(1).max_queue(1),
+        &in_flight,
+    );
+
+    let urls = ["http://a.com/", "http://b.com/", "http://c.com/"];
+    let results = join_all(urls.iter().map(|url| client.get(*url).send())).await;
+
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    let err = results.into_iter().nth(2).unwrap().unwrap_err();
+    assert!(matches!(limit_error(err), ConcurrencyLimitError::QueueFull));
+}
+
+#[tokio::test(start_paused = true)]
+async fn times_out_in_the_queue() {
+    let in_flight = Arc::new(InFlight::default());
+    let client = client(
+        ConcurrencyLimitMiddleware::global(1).queue_timeout(Duration::from_millis(500)),
+        &in_flight,
+    );
+
+    let (first, second) = tokio::join!(
+        client.get("http://a.com/").send(),
+        client.get("http://a.com/").send()
+    );
+    first.unwrap();
+    assert!(matches!(
+        limit_error(second.unwrap_err()),
+        ConcurrencyLimitError::QueueTimeout { timeout } if timeout == Duration::from_millis(500)
+    ));
+}
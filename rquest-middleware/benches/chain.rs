@@ -0,0 +1,88 @@
+//! Compares the cost of going through a chain of dynamically dispatched `Middleware` with a
+//! `StaticStack` of the same depth.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use http::Extensions;
+use rquest::{Method, Request};
+use rquest_middleware::{
+    ClientBuilder, ClientWithMiddleware, Middleware, Next, Response, Result, StaticMiddleware,
+    StaticNext, StaticStack, Transport,
+};
+
+/// Answers every request without touching the network.
+struct NoopTransport;
+
+#[async_trait::async_trait]
+impl Transport for NoopTransport {
+    async fn execute(&self, _req: Request, _extensions: &mut Extensions) -> Result<Response> {
+        Response::builder().body("")
+    }
+}
+
+struct Passthrough;
+
+#[async_trait::async_trait]
+impl Middleware for Passthrough {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        next.run(req, extensions).await
+    }
+}
+
+impl StaticMiddleware for Passthrough {
+    async fn handle<N: StaticNext>(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: N,
+    ) -> Result<Response> {
+        next.run(req, extensions).await
+    }
+}
+
+fn builder() -> ClientBuilder {
+    ClientBuilder::new(rquest::Client::new()).with_transport(NoopTransport)
+}
+
+fn dyn_client() -> ClientWithMiddleware {
+    (0..8)
+        .fold(builder(), |builder, _| builder.with(Passthrough))
+        .build()
+}
+
+fn static_client() -> ClientWithMiddleware {
+    let stack = StaticStack::new()
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough);
+    builder().with(stack).build()
+}
+
+fn chain(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let url: rquest::Url = "http://example.com".parse().unwrap();
+
+    let mut group = c.benchmark_group("chain of 8 middleware");
+    for (name, client) in [("dyn", dyn_client()), ("static", static_client())] {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &client, |b, client| {
+            b.iter(|| {
+                let req = Request::new(Method::GET, url.clone());
+                runtime.block_on(client.execute(req)).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, chain);
+criterion_main!(benches);
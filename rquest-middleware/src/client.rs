@@ -9,26 +9,40 @@ use std::sync::Arc;
 #[cfg(feature = "multipart")]
 use rquest::multipart;
 
+use crate::conditional::{Conditional, RequestMatcher};
 use crate::error::Result;
+use crate::hooks::{OnRequest, OnResponse};
 use crate::middleware::{Middleware, Next};
-use crate::RequestInitialiser;
 use crate::response::Response;
+use crate::swap::{self, MiddlewareStack, StackHandle};
+use crate::transport::Transport;
+#[cfg(feature = "websocket")]
+use crate::websocket::WebSocketRequestBuilder;
+use crate::{AsyncRequestInitialiser, RequestInitialiser};
 
 /// A `ClientBuilder` is used to build a [`ClientWithMiddleware`].
 ///
 /// [`ClientWithMiddleware`]: crate::ClientWithMiddleware
 pub struct ClientBuilder {
     client: Client,
+    transport: Arc<dyn Transport>,
     middleware_stack: Vec<Arc<dyn Middleware>>,
     initialiser_stack: Vec<Arc<dyn RequestInitialiser>>,
+    async_initialiser_stack: Vec<Arc<dyn AsyncRequestInitialiser>>,
+    request_hooks: Vec<Arc<dyn OnRequest>>,
+    response_hooks: Vec<Arc<dyn OnResponse>>,
 }
 
 impl ClientBuilder {
     pub fn new(client: Client) -> Self {
         ClientBuilder {
+            transport: Arc::new(client.clone()),
             client,
             middleware_stack: Vec::new(),
             initialiser_stack: Vec::new(),
+            async_initialiser_stack: Vec::new(),
+            request_hooks: Vec::new(),
+            response_hooks: Vec::new(),
         }
     }
 
@@ -37,11 +51,42 @@ impl ClientBuilder {
     pub fn from_client(client_with_middleware: ClientWithMiddleware) -> Self {
         Self {
             client: client_with_middleware.inner,
-            middleware_stack: client_with_middleware.middleware_stack.into_vec(),
+            transport: client_with_middleware.transport,
+            middleware_stack: client_with_middleware.middleware_stack.load().to_vec(),
             initialiser_stack: client_with_middleware.initialiser_stack.into_vec(),
+            async_initialiser_stack: client_with_middleware.async_initialiser_stack.into_vec(),
+            request_hooks: client_with_middleware.request_hooks.into_vec(),
+            response_hooks: client_with_middleware.response_hooks.into_vec(),
         }
     }
 
+    /// Convenience method to set the [`Transport`] used to send requests once they went through
+    /// the middleware chain. By default requests are sent with the [`rquest::Client`] given to
+    /// [`ClientBuilder::new`].
+    ///
+    /// The `rquest::Client` is still used to build requests, e.g. with
+    /// [`ClientWithMiddleware::get`].
+    ///
+    /// If you need to keep a reference to the transport after attaching, use
+    /// [`with_arc_transport`].
+    ///
+    /// [`with_arc_transport`]: Self::with_arc_transport
+    pub fn with_transport<T>(self, transport: T) -> Self
+    where
+        T: Transport,
+    {
+        self.with_arc_transport(Arc::new(transport))
+    }
+
+    /// Set the [`Transport`] used to send requests. [`with_transport`] is more ergonomic if you
+    /// don't need the `Arc`.
+    ///
+    /// [`with_transport`]: Self::with_transport
+    pub fn with_arc_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
     /// Convenience method to attach middleware.
     ///
     /// If you need to keep a reference to the middleware after attaching, use [`with_arc`].
@@ -62,6 +107,115 @@ impl ClientBuilder {
         self
     }
 
+    /// Convenience method to insert middleware at position `index` in the chain, shifting the
+    /// middleware after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of attached middleware.
+    pub fn insert<M>(self, index: usize, middleware: M) -> Self
+    where
+        M: Middleware,
+    {
+        self.insert_arc(index, Arc::new(middleware))
+    }
+
+    /// Insert middleware at position `index` in the chain. [`insert`] is more ergonomic if you
+    /// don't need the `Arc`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of attached middleware.
+    ///
+    /// [`insert`]: Self::insert
+    pub fn insert_arc(mut self, index: usize, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware_stack.insert(index, middleware);
+        self
+    }
+
+    /// Removes every middleware of type `M` from the chain, including wrapped ones such as
+    /// [`Named`](crate::Named) or [`Conditional`].
+    pub fn without<M>(mut self) -> Self
+    where
+        M: Middleware,
+    {
+        self.middleware_stack
+            .retain(|middleware| !is_of_type::<M>(&**middleware));
+        self
+    }
+
+    /// Removes every middleware whose [`Middleware::name`] is `name` from the chain.
+    pub fn without_named(mut self, name: &str) -> Self {
+        self.middleware_stack
+            .retain(|middleware| middleware.name() != name);
+        self
+    }
+
+    /// Replaces every middleware of type `M` in the chain with `middleware`, keeping its
+    /// position.
+    pub fn replace<M, R>(self, middleware: R) -> Self
+    where
+        M: Middleware,
+        R: Middleware,
+    {
+        self.replace_where(is_of_type::<M>, Arc::new(middleware))
+    }
+
+    /// Replaces every middleware whose [`Middleware::name`] is `name` with `middleware`, keeping
+    /// its position.
+    pub fn replace_named<R>(self, name: &str, middleware: R) -> Self
+    where
+        R: Middleware,
+    {
+        self.replace_where(|current| current.name() == name, Arc::new(middleware))
+    }
+
+    fn replace_where<F>(mut self, matches: F, middleware: Arc<dyn Middleware>) -> Self
+    where
+        F: Fn(&dyn Middleware) -> bool,
+    {
+        for current in self.middleware_stack.iter_mut() {
+            if matches(&**current) {
+                *current = middleware.clone();
+            }
+        }
+        self
+    }
+
+    /// Convenience method to attach middleware which only runs for requests accepted by
+    /// `matcher`, see [`Conditional`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rquest_middleware::matchers::{host, path_prefix};
+    /// use rquest_middleware::{ClientBuilder, RequestMatcher};
+    /// # use rquest_middleware::{Middleware, Next, Response, Result};
+    /// # struct AuthMiddleware;
+    /// # #[async_trait::async_trait]
+    /// # impl Middleware for AuthMiddleware {
+    /// #     async fn handle(
+    /// #         &self,
+    /// #         req: rquest::Request,
+    /// #         extensions: &mut http::Extensions,
+    /// #         next: Next<'_>,
+    /// #     ) -> Result<Response> {
+    /// #         next.run(req, extensions).await
+    /// #     }
+    /// # }
+    ///
+    /// let client = ClientBuilder::new(rquest::Client::new())
+    ///     .with_if(host("*.internal").and(path_prefix("/api")), AuthMiddleware)
+    ///     .build();
+    /// ```
+    pub fn with_if<P, M>(self, matcher: P, middleware: M) -> Self
+    where
+        P: RequestMatcher,
+        M: Middleware,
+    {
+        self.with(Conditional::new(matcher, middleware))
+    }
+
     /// Convenience method to attach a request initialiser.
     ///
     /// If you need to keep a reference to the initialiser after attaching, use [`with_arc_init`].
@@ -82,23 +236,142 @@ impl ClientBuilder {
         self
     }
 
+    /// Convenience method to attach an async request initialiser.
+    ///
+    /// If you need to keep a reference to the initialiser after attaching, use
+    /// [`with_arc_async_init`].
+    ///
+    /// [`with_arc_async_init`]: Self::with_arc_async_init
+    pub fn with_async_init<I>(self, initialiser: I) -> Self
+    where
+        I: AsyncRequestInitialiser,
+    {
+        self.with_arc_async_init(Arc::new(initialiser))
+    }
+
+    /// Add an async request initialiser to the chain. [`with_async_init`] is more ergonomic if you
+    /// don't need the `Arc`.
+    ///
+    /// [`with_async_init`]: Self::with_async_init
+    pub fn with_arc_async_init(mut self, initialiser: Arc<dyn AsyncRequestInitialiser>) -> Self {
+        self.async_initialiser_stack.push(initialiser);
+        self
+    }
+
+    /// Convenience method to attach a hook run on requests before the middleware chain.
+    ///
+    /// If you need to keep a reference to the hook after attaching, use [`with_arc_on_request`].
+    ///
+    /// [`with_arc_on_request`]: Self::with_arc_on_request
+    pub fn on_request<H>(self, hook: H) -> Self
+    where
+        H: OnRequest,
+    {
+        self.with_arc_on_request(Arc::new(hook))
+    }
+
+    /// Add a hook run on requests before the middleware chain. [`on_request`] is more ergonomic
+    /// if you don't need the `Arc`.
+    ///
+    /// [`on_request`]: Self::on_request
+    pub fn with_arc_on_request(mut self, hook: Arc<dyn OnRequest>) -> Self {
+        self.request_hooks.push(hook);
+        self
+    }
+
+    /// Convenience method to attach a hook run on the result of the middleware chain.
+    ///
+    /// If you need to keep a reference to the hook after attaching, use [`with_arc_on_response`].
+    ///
+    /// [`with_arc_on_response`]: Self::with_arc_on_response
+    pub fn on_response<H>(self, hook: H) -> Self
+    where
+        H: OnResponse,
+    {
+        self.with_arc_on_response(Arc::new(hook))
+    }
+
+    /// Add a hook run on the result of the middleware chain. [`on_response`] is more ergonomic if
+    /// you don't need the `Arc`.
+    ///
+    /// [`on_response`]: Self::on_response
+    pub fn with_arc_on_response(mut self, hook: Arc<dyn OnResponse>) -> Self {
+        self.response_hooks.push(hook);
+        self
+    }
+
+    /// Returns an iterator over the attached middleware, in the order they run.
+    pub fn middleware(&self) -> impl ExactSizeIterator<Item = &dyn Middleware> + '_ {
+        self.middleware_stack.iter().map(|middleware| &**middleware)
+    }
+
+    /// Returns an iterator over the attached request initialisers, in the order they run.
+    pub fn initialisers(&self) -> impl ExactSizeIterator<Item = &dyn RequestInitialiser> + '_ {
+        self.initialiser_stack
+            .iter()
+            .map(|initialiser| &**initialiser)
+    }
+
+    /// Returns an iterator over the attached async request initialisers, in the order they run.
+    pub fn async_initialisers(
+        &self,
+    ) -> impl ExactSizeIterator<Item = &dyn AsyncRequestInitialiser> + '_ {
+        self.async_initialiser_stack
+            .iter()
+            .map(|initialiser| &**initialiser)
+    }
+
     /// Returns a `ClientWithMiddleware` using this builder configuration.
     pub fn build(self) -> ClientWithMiddleware {
+        self.build_with(MiddlewareStack::fixed)
+    }
+
+    /// Returns a `ClientWithMiddleware` using this builder configuration, whose middleware can be
+    /// replaced at runtime with the returned [`StackHandle`].
+    ///
+    /// Clients derived from it with [`ClientWithMiddleware::derive`] start from a copy of its
+    /// current middleware, they don't share the handle.
+    pub fn build_swappable(self) -> (ClientWithMiddleware, StackHandle) {
+        let client = self.build_with(MiddlewareStack::swappable);
+        let handle = client
+            .stack_handle()
+            .expect("the middleware stack is swappable");
+        (client, handle)
+    }
+
+    fn build_with(
+        self,
+        middleware_stack: fn(Vec<Arc<dyn Middleware>>) -> MiddlewareStack,
+    ) -> ClientWithMiddleware {
         ClientWithMiddleware {
             inner: self.client,
-            middleware_stack: self.middleware_stack.into_boxed_slice(),
+            transport: self.transport,
+            middleware_stack: middleware_stack(self.middleware_stack),
             initialiser_stack: self.initialiser_stack.into_boxed_slice(),
+            async_initialiser_stack: self.async_initialiser_stack.into_boxed_slice(),
+            request_hooks: self.request_hooks.into_boxed_slice(),
+            response_hooks: self.response_hooks.into_boxed_slice(),
         }
     }
 }
 
 /// `ClientWithMiddleware` is a wrapper around [`rquest::Client`] which runs middleware on every
 /// request.
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct ClientWithMiddleware {
-    inner: rquest::Client,
-    middleware_stack: Box<[Arc<dyn Middleware>]>,
+    pub(crate) inner: rquest::Client,
+    transport: Arc<dyn Transport>,
+    middleware_stack: MiddlewareStack,
     initialiser_stack: Box<[Arc<dyn RequestInitialiser>]>,
+    async_initialiser_stack: Box<[Arc<dyn AsyncRequestInitialiser>]>,
+    request_hooks: Box<[Arc<dyn OnRequest>]>,
+    response_hooks: Box<[Arc<dyn OnResponse>]>,
+}
+
+impl Default for ClientWithMiddleware {
+    fn default() -> Self {
+        Client::default().into()
+    }
 }
 
 impl ClientWithMiddleware {
@@ -108,13 +381,72 @@ impl ClientWithMiddleware {
         T: Into<Box<[Arc<dyn Middleware>]>>,
     {
         ClientWithMiddleware {
+            transport: Arc::new(client.clone()),
             inner: client,
-            middleware_stack: middleware_stack.into(),
+            middleware_stack: MiddlewareStack::fixed(middleware_stack.into().into_vec()),
             // TODO(conradludgate) - allow downstream code to control this manually if desired
             initialiser_stack: Box::new([]),
+            async_initialiser_stack: Box::new([]),
+            request_hooks: Box::new([]),
+            response_hooks: Box::new([]),
         }
     }
 
+    /// Returns an iterator over the current middleware, in the order they run.
+    pub fn middleware(&self) -> impl ExactSizeIterator<Item = Arc<dyn Middleware>> {
+        swap::iter(self.middleware_stack.load())
+    }
+
+    /// Returns the handle to replace the middleware of this client, if it was built with
+    /// [`ClientBuilder::build_swappable`].
+    pub fn stack_handle(&self) -> Option<StackHandle> {
+        self.middleware_stack.handle()
+    }
+
+    /// Returns an iterator over the attached request initialisers, in the order they run.
+    pub fn initialisers(&self) -> impl ExactSizeIterator<Item = &dyn RequestInitialiser> + '_ {
+        self.initialiser_stack
+            .iter()
+            .map(|initialiser| &**initialiser)
+    }
+
+    /// Returns an iterator over the attached async request initialisers, in the order they run.
+    pub fn async_initialisers(
+        &self,
+    ) -> impl ExactSizeIterator<Item = &dyn AsyncRequestInitialiser> + '_ {
+        self.async_initialiser_stack
+            .iter()
+            .map(|initialiser| &**initialiser)
+    }
+
+    /// Creates a [`ClientBuilder`] starting from the configuration of this client, to derive a
+    /// new client with different middleware, initialisers or hooks.
+    ///
+    /// This is cheap: the underlying `rquest::Client`, transport and middleware are shared
+    /// between both clients, not copied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rquest_middleware::ClientBuilder;
+    /// use rquest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+    ///
+    /// let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
+    /// let base = ClientBuilder::new(rquest::Client::new())
+    ///     .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+    ///     .build();
+    ///
+    /// // The same client, without retries.
+    /// let no_retry = base
+    ///     .derive()
+    ///     .without::<RetryTransientMiddleware<ExponentialBackoff>>()
+    ///     .build();
+    /// assert_eq!(no_retry.middleware().len(), 0);
+    /// ```
+    pub fn derive(&self) -> ClientBuilder {
+        ClientBuilder::from_client(self.clone())
+    }
+
     /// Convenience method to make a `GET` request to a URL.
     ///
     /// # Errors
@@ -180,9 +512,8 @@ impl ClientWithMiddleware {
     pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
         let req = RequestBuilder {
             inner: self.inner.request(method, url),
+            client: self.clone(),
             extensions: Extensions::new(),
-            middleware_stack: self.middleware_stack.clone(),
-            initialiser_stack: self.initialiser_stack.clone(),
         };
         self.initialiser_stack
             .iter()
@@ -223,17 +554,57 @@ impl ClientWithMiddleware {
         req: Request,
         ext: &mut Extensions,
     ) -> Result<Response> {
-        let next = Next::new(&self.inner, &self.middleware_stack);
-        next.run(req, ext).await
+        self.run_chain(&*self.transport, req, ext).await
     }
 
-    /// Upgrades the [`RequestBuilder`] to perform a
-    /// websocket handshake. This returns a wrapped type, so you must do
-    /// this after you set up your request, and just before you send the
-    /// request.
+    /// Sends the request through the initialisers, hooks and middleware, down to `transport`.
+    pub(crate) async fn run_chain(
+        &self,
+        transport: &dyn Transport,
+        mut req: Request,
+        ext: &mut Extensions,
+    ) -> Result<Response> {
+        self.init_request(&mut req, ext).await?;
+
+        let mut result = match self.run_request_hooks(&mut req, ext) {
+            Ok(()) => {
+                let middleware_stack = self.middleware_stack.load();
+                Next::new(transport, &middleware_stack).run(req, ext).await
+            }
+            Err(err) => Err(err),
+        };
+        for hook in self.response_hooks.iter() {
+            hook.on_response(&mut result, ext);
+        }
+        result
+    }
+
+    fn run_request_hooks(&self, req: &mut Request, ext: &mut Extensions) -> Result<()> {
+        for hook in self.request_hooks.iter() {
+            hook.on_request(req, ext)?;
+        }
+        Ok(())
+    }
+
+    /// Runs [`RequestInitialiser::init_request`] for every initialiser, then every
+    /// [`AsyncRequestInitialiser`], in the order they were attached.
+    async fn init_request(&self, req: &mut Request, ext: &mut Extensions) -> Result<()> {
+        for initialiser in self.initialiser_stack.iter() {
+            initialiser.init_request(req, ext);
+        }
+        for initialiser in self.async_initialiser_stack.iter() {
+            initialiser.init(req, ext).await?;
+        }
+        Ok(())
+    }
+
+    /// Start building a websocket handshake request to the `Url`.
+    ///
+    /// Request initialisers run as for any other request, and the handshake request goes
+    /// through the middleware chain when sent, see [`WebSocketRequestBuilder`].
     #[cfg(feature = "websocket")]
-    pub fn websocket<U: IntoUrl>(&self, url: U) -> rquest::WebSocketRequestBuilder {
-        self.inner.websocket(url)
+    pub fn websocket<U: IntoUrl>(&self, url: U) -> WebSocketRequestBuilder {
+        WebSocketRequestBuilder::new(self.request(Method::GET, url))
     }
 
     /// Retrieves the `User-Agent` header for this client.
@@ -253,18 +624,40 @@ impl ClientWithMiddleware {
 impl From<Client> for ClientWithMiddleware {
     fn from(client: Client) -> Self {
         ClientWithMiddleware {
+            transport: Arc::new(client.clone()),
             inner: client,
-            middleware_stack: Box::new([]),
+            middleware_stack: MiddlewareStack::fixed(Vec::new()),
             initialiser_stack: Box::new([]),
+            async_initialiser_stack: Box::new([]),
+            request_hooks: Box::new([]),
+            response_hooks: Box::new([]),
         }
     }
 }
 
+/// Returns `true` if `middleware` is an `M`, possibly wrapped.
+fn is_of_type<M: Middleware>(middleware: &dyn Middleware) -> bool {
+    middleware.middleware_type_id() == std::any::TypeId::of::<M>()
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("client", &self.client)
+            .field("initialisers", &self.initialiser_stack)
+            .field("async_initialisers", &self.async_initialiser_stack)
+            .field("middleware", &self.middleware_stack)
+            .finish_non_exhaustive()
+    }
+}
+
 impl fmt::Debug for ClientWithMiddleware {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // skipping middleware_stack field for now
         f.debug_struct("ClientWithMiddleware")
             .field("inner", &self.inner)
+            .field("initialisers", &self.initialiser_stack)
+            .field("async_initialisers", &self.async_initialiser_stack)
+            .field("middleware", &self.middleware_stack.load())
             .finish_non_exhaustive()
     }
 }
@@ -277,12 +670,11 @@ mod service {
         task::{Context, Poll},
     };
 
+    use crate::response::Response;
     use crate::Result;
-    use http::Extensions;
     use rquest::Request;
-    use crate::response::Response;
 
-    use crate::{middleware::BoxFuture, ClientWithMiddleware, Next};
+    use crate::{middleware::BoxFuture, ClientWithMiddleware};
 
     // this is meant to be semi-private, same as rquest's pending
     pub struct Pending {
@@ -309,14 +701,9 @@ mod service {
         }
 
         fn call(&mut self, req: Request) -> Self::Future {
-            let inner = self.inner.clone();
-            let middlewares = self.middleware_stack.clone();
-            let mut extensions = Extensions::new();
+            let client = self.clone();
             Pending {
-                inner: Box::pin(async move {
-                    let next = Next::new(&inner, &middlewares);
-                    next.run(req, &mut extensions).await
-                }),
+                inner: Box::pin(async move { client.execute(req).await }),
             }
         }
     }
@@ -331,14 +718,9 @@ mod service {
         }
 
         fn call(&mut self, req: Request) -> Self::Future {
-            let inner = self.inner.clone();
-            let middlewares = self.middleware_stack.clone();
-            let mut extensions = Extensions::new();
+            let client = self.clone();
             Pending {
-                inner: Box::pin(async move {
-                    let next = Next::new(&inner, &middlewares);
-                    next.run(req, &mut extensions).await
-                }),
+                inner: Box::pin(async move { client.execute(req).await }),
             }
         }
     }
@@ -348,19 +730,17 @@ mod service {
 #[must_use = "RequestBuilder does nothing until you 'send' it"]
 pub struct RequestBuilder {
     inner: rquest::RequestBuilder,
-    middleware_stack: Box<[Arc<dyn Middleware>]>,
-    initialiser_stack: Box<[Arc<dyn RequestInitialiser>]>,
+    client: ClientWithMiddleware,
     extensions: Extensions,
 }
 
 impl RequestBuilder {
     /// Assemble a builder starting from an existing `Client` and a `Request`.
     pub fn from_parts(client: ClientWithMiddleware, request: Request) -> RequestBuilder {
-        let inner = rquest::RequestBuilder::from_parts(client.inner, request);
+        let inner = rquest::RequestBuilder::from_parts(client.inner.clone(), request);
         RequestBuilder {
             inner,
-            middleware_stack: client.middleware_stack,
-            initialiser_stack: client.initialiser_stack,
+            client,
             extensions: Extensions::new(),
         }
     }
@@ -553,18 +933,8 @@ impl RequestBuilder {
     /// This is similar to [`RequestBuilder::build()`], but also returns the
     /// embedded `Client`.
     pub fn build_split(self) -> (ClientWithMiddleware, rquest::Result<Request>) {
-        let Self {
-            inner,
-            middleware_stack,
-            initialiser_stack,
-            ..
-        } = self;
-        let (inner, req) = inner.build_split();
-        let client = ClientWithMiddleware {
-            inner,
-            middleware_stack,
-            initialiser_stack,
-        };
+        let Self { inner, client, .. } = self;
+        let (_, req) = inner.build_split();
         (client, req)
     }
 
@@ -600,10 +970,42 @@ impl RequestBuilder {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn send(mut self) -> Result<Response> {
+    pub async fn send(self) -> Result<Response> {
+        let (res, _) = self.send_with_extensions().await;
+        res
+    }
+
+    /// Constructs the Request and sends it to the target URL, like [`send`](Self::send), and
+    /// returns the [`Extensions`] as left by the initialisers and middleware along with the
+    /// result.
+    ///
+    /// The extensions are returned even when the request failed, e.g. to inspect what a
+    /// middleware recorded about the failed attempts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anyhow::Error;
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// #[derive(Clone)]
+    /// struct Attempts(u32);
+    ///
+    /// let client = rquest_middleware::ClientWithMiddleware::from(rquest::Client::new());
+    /// let (response, extensions) = client.get("https://hyper.rs").send_with_extensions().await;
+    /// let attempts = extensions.get::<Attempts>().map_or(1, |attempts| attempts.0);
+    /// println!("{} after {attempts} attempts", response?.status());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_with_extensions(mut self) -> (Result<Response>, Extensions) {
         let mut extensions = std::mem::take(self.extensions());
         let (client, req) = self.build_split();
-        client.execute_with_extensions(req?, &mut extensions).await
+        let res = match req {
+            Ok(req) => client.execute_with_extensions(req, &mut extensions).await,
+            Err(err) => Err(err.into()),
+        };
+        (res, extensions)
     }
 
     /// Attempt to clone the RequestBuilder.
@@ -628,8 +1030,7 @@ impl RequestBuilder {
     pub fn try_clone(&self) -> Option<Self> {
         self.inner.try_clone().map(|inner| RequestBuilder {
             inner,
-            middleware_stack: self.middleware_stack.clone(),
-            initialiser_stack: self.initialiser_stack.clone(),
+            client: self.client.clone(),
             extensions: self.extensions.clone(),
         })
     }
@@ -0,0 +1,134 @@
+use http::Extensions;
+use rquest::Request;
+
+use crate::error::Result;
+use crate::matchers::{And, Not, Or};
+use crate::middleware::{Middleware, Next};
+use crate::response::Response;
+
+/// Decides whether a request should go through a [`Conditional`] middleware.
+///
+/// See the [`matchers`](crate::matchers) module for built-in matchers. Closures taking the
+/// request and its extensions are matchers too.
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::matchers::{host, methods};
+/// use rquest_middleware::RequestMatcher;
+///
+/// let internal_reads = host("*.internal.example.com").and(methods(["GET", "HEAD"]));
+/// ```
+pub trait RequestMatcher: 'static + Send + Sync {
+    /// Returns `true` if the middleware should run for the request.
+    fn matches(&self, req: &Request, extensions: &Extensions) -> bool;
+
+    /// Matches requests matched by both `self` and `other`.
+    fn and<M: RequestMatcher>(self, other: M) -> And<Self, M>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Matches requests matched by either `self` or `other`.
+    fn or<M: RequestMatcher>(self, other: M) -> Or<Self, M>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Matches requests not matched by `self`.
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+impl<F> RequestMatcher for F
+where
+    F: Send + Sync + 'static + Fn(&Request, &Extensions) -> bool,
+{
+    fn matches(&self, req: &Request, extensions: &Extensions) -> bool {
+        (self)(req, extensions)
+    }
+}
+
+/// Runs the wrapped middleware only for requests accepted by the matcher. Other requests skip it
+/// and go straight to the rest of the chain.
+///
+/// Generally attached using [`ClientBuilder::with_if`](crate::ClientBuilder::with_if).
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::matchers::host;
+/// use rquest_middleware::{ClientBuilder, Conditional};
+/// # use rquest_middleware::{Middleware, Next, Response, Result};
+/// # struct AuthMiddleware;
+/// # #[async_trait::async_trait]
+/// # impl Middleware for AuthMiddleware {
+/// #     async fn handle(
+/// #         &self,
+/// #         req: rquest::Request,
+/// #         extensions: &mut http::Extensions,
+/// #         next: Next<'_>,
+/// #     ) -> Result<Response> {
+/// #         next.run(req, extensions).await
+/// #     }
+/// # }
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(Conditional::new(host("api.example.com"), AuthMiddleware))
+///     .build();
+/// ```
+pub struct Conditional<P, M> {
+    matcher: P,
+    middleware: M,
+}
+
+impl<P, M> Conditional<P, M>
+where
+    P: RequestMatcher,
+    M: Middleware,
+{
+    /// Wraps `middleware` so it only runs for requests accepted by `matcher`.
+    pub fn new(matcher: P, middleware: M) -> Self {
+        Self {
+            matcher,
+            middleware,
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P, M> Middleware for Conditional<P, M>
+where
+    P: RequestMatcher,
+    M: Middleware,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        if self.matcher.matches(&req, extensions) {
+            self.middleware.handle(req, extensions, next).await
+        } else {
+            next.run(req, extensions).await
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.middleware.name()
+    }
+
+    fn middleware_type_id(&self) -> std::any::TypeId {
+        self.middleware.middleware_type_id()
+    }
+}
@@ -1,4 +1,7 @@
+use bytes::Bytes;
+use rquest::header::HeaderMap;
 use rquest::{StatusCode, Url};
+use std::fmt;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -11,7 +14,10 @@ pub enum Error {
     /// Error from the underlying rquest client
     #[error(transparent)]
     Rquest(#[from] rquest::Error),
-    
+    /// The server responded with a client or server error status, see
+    /// [`Response::error_for_status`](crate::Response::error_for_status)
+    #[error(transparent)]
+    Status(Box<StatusError>),
 }
 
 impl Error {
@@ -27,6 +33,7 @@ impl Error {
         match self {
             Error::Middleware(_) => None,
             Error::Rquest(e) => e.url(),
+            Error::Status(e) => e.url(),
         }
     }
 
@@ -39,6 +46,7 @@ impl Error {
         match self {
             Error::Middleware(_) => None,
             Error::Rquest(e) => e.url_mut(),
+            Error::Status(e) => e.url.as_mut(),
         }
     }
 
@@ -47,6 +55,10 @@ impl Error {
         match self {
             Error::Middleware(_) => self,
             Error::Rquest(e) => e.with_url(url).into(),
+            Error::Status(mut e) => {
+                e.url = Some(url);
+                Error::Status(e)
+            }
         }
     }
 
@@ -56,6 +68,10 @@ impl Error {
         match self {
             Error::Middleware(_) => self,
             Error::Rquest(e) => e.without_url().into(),
+            Error::Status(mut e) => {
+                e.url = None;
+                Error::Status(e)
+            }
         }
     }
 
@@ -63,14 +79,14 @@ impl Error {
     pub fn is_middleware(&self) -> bool {
         match self {
             Error::Middleware(_) => true,
-            Error::Rquest(_) => false,
+            Error::Rquest(_) | Error::Status(_) => false,
         }
     }
 
     /// Returns true if the error is from a type `Builder`.
     pub fn is_builder(&self) -> bool {
         match self {
-            Error::Middleware(_) => false,
+            Error::Middleware(_) | Error::Status(_) => false,
             Error::Rquest(e) => e.is_builder(),
         }
     }
@@ -78,7 +94,7 @@ impl Error {
     /// Returns true if the error is from a `RedirectPolicy`.
     pub fn is_redirect(&self) -> bool {
         match self {
-            Error::Middleware(_) => false,
+            Error::Middleware(_) | Error::Status(_) => false,
             Error::Rquest(e) => e.is_redirect(),
         }
     }
@@ -88,13 +104,14 @@ impl Error {
         match self {
             Error::Middleware(_) => false,
             Error::Rquest(e) => e.is_status(),
+            Error::Status(_) => true,
         }
     }
 
     /// Returns true if the error is related to a timeout.
     pub fn is_timeout(&self) -> bool {
         match self {
-            Error::Middleware(_) => false,
+            Error::Middleware(_) | Error::Status(_) => false,
             Error::Rquest(e) => e.is_timeout(),
         }
     }
@@ -102,7 +119,7 @@ impl Error {
     /// Returns true if the error is related to the request.
     pub fn is_request(&self) -> bool {
         match self {
-            Error::Middleware(_) => false,
+            Error::Middleware(_) | Error::Status(_) => false,
             Error::Rquest(e) => e.is_request(),
         }
     }
@@ -111,7 +128,7 @@ impl Error {
     /// Returns true if the error is related to connect.
     pub fn is_connect(&self) -> bool {
         match self {
-            Error::Middleware(_) => false,
+            Error::Middleware(_) | Error::Status(_) => false,
             Error::Rquest(e) => e.is_connect(),
         }
     }
@@ -119,7 +136,7 @@ impl Error {
     /// Returns true if the error is related to the request or response body.
     pub fn is_body(&self) -> bool {
         match self {
-            Error::Middleware(_) => false,
+            Error::Middleware(_) | Error::Status(_) => false,
             Error::Rquest(e) => e.is_body(),
         }
     }
@@ -127,7 +144,7 @@ impl Error {
     /// Returns true if the error is related to decoding the response's body.
     pub fn is_decode(&self) -> bool {
         match self {
-            Error::Middleware(_) => false,
+            Error::Middleware(_) | Error::Status(_) => false,
             Error::Rquest(e) => e.is_decode(),
         }
     }
@@ -137,6 +154,68 @@ impl Error {
         match self {
             Error::Middleware(_) => None,
             Error::Rquest(e) => e.status(),
+            Error::Status(e) => Some(e.status()),
+        }
+    }
+}
+
+/// A client or server error status returned by the server, created by
+/// [`Response::error_for_status`](crate::Response::error_for_status).
+///
+/// Keeps the status, headers, url and buffered body of the failing response.
+#[derive(Debug)]
+pub struct StatusError {
+    status: StatusCode,
+    headers: HeaderMap,
+    url: Option<Url>,
+    body: Bytes,
+}
+
+impl StatusError {
+    pub(crate) fn new(status: StatusCode, headers: HeaderMap, url: Url, body: Bytes) -> Self {
+        Self {
+            status,
+            headers,
+            url: Some(url),
+            body,
         }
     }
+
+    /// Returns the status code of the failing response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the headers of the failing response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the url of the failing response, unless it was stripped with
+    /// [`Error::without_url`].
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    /// Returns the body of the failing response.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
 }
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.status.is_client_error() {
+            "client"
+        } else {
+            "server"
+        };
+        write!(f, "HTTP status {} error ({})", kind, self.status)?;
+        if let Some(url) = &self.url {
+            write!(f, " for url ({})", url)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for StatusError {}
@@ -0,0 +1,278 @@
+use std::future::Future;
+
+use futures_channel::oneshot;
+use futures_util::future::{self, Either};
+use http::Extensions;
+use rquest::Request;
+use thiserror::Error;
+
+use crate::error::{Error, Result};
+use crate::middleware::{Middleware, Next};
+use crate::response::Response;
+
+/// What [`FnNext`] hands back to [`FromFn`]: the extensions, along with the request to forward
+/// down the chain and where to send the result, unless the closure answered on its own.
+type Forward = (
+    Option<(Request, oneshot::Sender<Result<Response>>)>,
+    Extensions,
+);
+
+/// Errors returned by [`FnNext::run`] through [`Error::Middleware`].
+#[derive(Debug, Error)]
+pub enum FnError {
+    #[error("The middleware chain was dropped before answering the request")]
+    Dropped,
+}
+
+/// Creates a middleware from an async closure, see [`FnNext`].
+///
+/// Unlike the [`Middleware`] implementation for closures, the closure doesn't have to return a
+/// [`BoxFuture`](crate::BoxFuture).
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::{from_fn, ClientBuilder};
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(from_fn(|req, next| async move {
+///         println!("Request started {req:?}");
+///         let res = next.run(req).await;
+///         println!("Result: {res:?}");
+///         res
+///     }))
+///     .build();
+/// ```
+pub fn from_fn<F, Fut>(f: F) -> FromFn<F>
+where
+    F: Fn(Request, FnNext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response>> + Send + 'static,
+{
+    FromFn { f }
+}
+
+/// A middleware created with [`from_fn`].
+pub struct FromFn<F> {
+    f: F,
+}
+
+/// The rest of the middleware chain given to closures passed to [`from_fn`].
+///
+/// It owns the request [`Extensions`] until the request is forwarded down the chain with
+/// [`FnNext::run`].
+pub struct FnNext {
+    inner: Option<(oneshot::Sender<Forward>, Extensions)>,
+}
+
+impl FnNext {
+    /// Returns a reference to the extensions of the request.
+    pub fn extensions(&self) -> &Extensions {
+        match &self.inner {
+            Some((_, extensions)) => extensions,
+            None => unreachable!("FnNext is only emptied when consumed"),
+        }
+    }
+
+    /// Returns a mutable reference to the extensions of the request.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        match &mut self.inner {
+            Some((_, extensions)) => extensions,
+            None => unreachable!("FnNext is only emptied when consumed"),
+        }
+    }
+
+    /// Forwards the request down the rest of the middleware chain, along with the extensions.
+    pub async fn run(mut self, req: Request) -> Result<Response> {
+        let (forward, extensions) = self.inner.take().expect("FnNext is only run once");
+        let (reply_tx, reply_rx) = oneshot::channel();
+        forward
+            .send((Some((req, reply_tx)), extensions))
+            .map_err(|_| Error::middleware(FnError::Dropped))?;
+        reply_rx
+            .await
+            .unwrap_or_else(|_| Err(Error::middleware(FnError::Dropped)))
+    }
+}
+
+impl Drop for FnNext {
+    fn drop(&mut self) {
+        // Give the extensions back if the closure answered without running the chain.
+        if let Some((forward, extensions)) = self.inner.take() {
+            let _ = forward.send((None, extensions));
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<F, Fut> Middleware for FromFn<F>
+where
+    F: Fn(Request, FnNext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response>> + Send + 'static,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let (forward_tx, mut forward_rx) = oneshot::channel::<Forward>();
+        let call = (self.f)(
+            req,
+            FnNext {
+                inner: Some((forward_tx, std::mem::take(extensions))),
+            },
+        );
+
+        let result = {
+            let forward = async {
+                if let Ok((request, returned)) = (&mut forward_rx).await {
+                    *extensions = returned;
+                    if let Some((request, reply)) = request {
+                        let _ = reply.send(next.run(request, extensions).await);
+                    }
+                }
+            };
+
+            futures_util::pin_mut!(call, forward);
+            match future::select(call, forward).await {
+                Either::Left((result, _)) => result,
+                Either::Right(((), call)) => call.await,
+            }
+        };
+
+        // The closure might have answered before the extensions it dropped were received.
+        if let Ok(Some((_, returned))) = forward_rx.try_recv() {
+            *extensions = returned;
+        }
+        result
+    }
+}
+
+/// Creates a middleware which modifies requests before they go down the chain.
+///
+/// # Example
+///
+/// ```
+/// use rquest::header::{HeaderValue, USER_AGENT};
+/// use rquest_middleware::{map_request, ClientBuilder};
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(map_request(|mut req| async move {
+///         req.headers_mut()
+///             .insert(USER_AGENT, HeaderValue::from_static("my-agent"));
+///         Ok(req)
+///     }))
+///     .build();
+/// ```
+pub fn map_request<F, Fut>(f: F) -> MapRequest<F>
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Request>> + Send + 'static,
+{
+    MapRequest { f }
+}
+
+/// A middleware created with [`map_request`].
+pub struct MapRequest<F> {
+    f: F,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<F, Fut> Middleware for MapRequest<F>
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Request>> + Send + 'static,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let req = (self.f)(req).await?;
+        next.run(req, extensions).await
+    }
+}
+
+/// Creates a middleware which modifies successful responses coming back from the chain.
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::{map_response, ClientBuilder};
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(map_response(|res| async move { res.error_for_status().await }))
+///     .build();
+/// ```
+pub fn map_response<F, Fut>(f: F) -> MapResponse<F>
+where
+    F: Fn(Response) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response>> + Send + 'static,
+{
+    MapResponse { f }
+}
+
+/// A middleware created with [`map_response`].
+pub struct MapResponse<F> {
+    f: F,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<F, Fut> Middleware for MapResponse<F>
+where
+    F: Fn(Response) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Response>> + Send + 'static,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let res = next.run(req, extensions).await?;
+        (self.f)(res).await
+    }
+}
+
+/// Creates a middleware which maps errors coming back from the chain.
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::{map_err, ClientBuilder, Error};
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(map_err(|err: Error| err.without_url()))
+///     .build();
+/// ```
+pub fn map_err<F>(f: F) -> MapErr<F>
+where
+    F: Fn(Error) -> Error + Send + Sync + 'static,
+{
+    MapErr { f }
+}
+
+/// A middleware created with [`map_err`].
+pub struct MapErr<F> {
+    f: F,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<F> Middleware for MapErr<F>
+where
+    F: Fn(Error) -> Error + Send + Sync + 'static,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        next.run(req, extensions).await.map_err(&self.f)
+    }
+}
@@ -0,0 +1,77 @@
+use http::Extensions;
+use rquest::Request;
+
+use crate::error::Result;
+use crate::response::Response;
+
+/// A hook looking at or modifying requests before they go through the middleware chain.
+///
+/// Hooks are registered with [`ClientBuilder::on_request`] and run in the order they were
+/// registered, after the request initialisers. They are cheaper than a [`Middleware`] as they
+/// don't wrap the rest of the chain.
+///
+/// Returning an error skips the middleware chain, the error is then given to the [`OnResponse`]
+/// hooks.
+///
+/// # Example
+///
+/// ```
+/// use rquest::header::{HeaderValue, USER_AGENT};
+/// use rquest_middleware::{ClientBuilder, Result};
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .on_request(|req: &mut rquest::Request, _: &mut http::Extensions| -> Result<()> {
+///         req.headers_mut()
+///             .insert(USER_AGENT, HeaderValue::from_static("my-agent"));
+///         Ok(())
+///     })
+///     .build();
+/// ```
+///
+/// [`ClientBuilder::on_request`]: crate::ClientBuilder::on_request
+/// [`Middleware`]: crate::Middleware
+pub trait OnRequest: 'static + Send + Sync {
+    fn on_request(&self, req: &mut Request, extensions: &mut Extensions) -> Result<()>;
+}
+
+impl<F> OnRequest for F
+where
+    F: Send + Sync + 'static + Fn(&mut Request, &mut Extensions) -> Result<()>,
+{
+    fn on_request(&self, req: &mut Request, extensions: &mut Extensions) -> Result<()> {
+        (self)(req, extensions)
+    }
+}
+
+/// A hook looking at or modifying the result of the middleware chain.
+///
+/// Hooks are registered with [`ClientBuilder::on_response`] and run in the order they were
+/// registered, once the middleware chain is done, whether it succeeded or failed.
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::{ClientBuilder, Response, Result};
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .on_response(|res: &mut Result<Response>, _: &mut http::Extensions| {
+///         if let Ok(res) = res {
+///             println!("{} responded with {}", res.url(), res.status());
+///         }
+///     })
+///     .build();
+/// ```
+///
+/// [`ClientBuilder::on_response`]: crate::ClientBuilder::on_response
+pub trait OnResponse: 'static + Send + Sync {
+    fn on_response(&self, result: &mut Result<Response>, extensions: &mut Extensions);
+}
+
+impl<F> OnResponse for F
+where
+    F: Send + Sync + 'static + Fn(&mut Result<Response>, &mut Extensions),
+{
+    fn on_response(&self, result: &mut Result<Response>, extensions: &mut Extensions) {
+        (self)(result, extensions)
+    }
+}
@@ -0,0 +1,166 @@
+use std::future::poll_fn;
+use std::sync::Mutex;
+
+use futures_channel::oneshot;
+use futures_util::future::{self, Either};
+use http::Extensions;
+use rquest::Request;
+use thiserror::Error;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::error::{Error, Result};
+use crate::middleware::{BoxFuture, Middleware, Next};
+use crate::response::Response;
+
+/// The request handled by the tower services built by a [`TowerMiddleware`].
+///
+/// It wraps the [`Request`] along with a handle to the rest of the middleware chain, which is
+/// invoked once the request reaches the innermost service.
+pub struct TowerRequest {
+    request: Request,
+    next: oneshot::Sender<Forward>,
+}
+
+/// A request forwarded by [`NextService`] to the rest of the chain, along with where to send the
+/// result.
+type Forward = (Request, oneshot::Sender<Result<Response>>);
+
+impl TowerRequest {
+    /// Returns a reference to the wrapped request.
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// Returns a mutable reference to the wrapped request.
+    pub fn request_mut(&mut self) -> &mut Request {
+        &mut self.request
+    }
+}
+
+/// Errors returned by [`TowerMiddleware`] through [`Error::Middleware`].
+#[derive(Debug, Error)]
+pub enum TowerError {
+    #[error("The tower service dropped the request before it reached the rest of the chain")]
+    Dropped,
+}
+
+/// The innermost tower service of a [`TowerMiddleware`], forwarding requests to the rest of the
+/// middleware chain.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NextService;
+
+impl Service<TowerRequest> for NextService {
+    type Response = Response;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<Response>>;
+
+    fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: TowerRequest) -> Self::Future {
+        let (reply, response) = oneshot::channel();
+        let sent = req.next.send((req.request, reply)).is_ok();
+        Box::pin(async move {
+            if !sent {
+                return Err(Error::middleware(TowerError::Dropped));
+            }
+            response
+                .await
+                .map_err(|_| Error::middleware(TowerError::Dropped))?
+        })
+    }
+}
+
+/// A middleware running requests through a [`tower_layer::Layer`], e.g. a timeout, concurrency
+/// limit, load-shed or buffer layer from the `tower` crate.
+///
+/// The layer is applied once, when the middleware is created, so any state it keeps (e.g. the
+/// semaphore of a concurrency limit) is shared by every request sent through it. The innermost
+/// service continues down the middleware chain with the original [`Extensions`].
+///
+/// Errors returned by the layer are mapped to [`Error::Middleware`], unless they are already
+/// [`Error`]s coming from the rest of the chain.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rquest_middleware::{ClientBuilder, TowerMiddleware};
+/// use tower::timeout::TimeoutLayer;
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(TowerMiddleware::new(TimeoutLayer::new(Duration::from_secs(10))))
+///     .build();
+/// ```
+///
+/// # Optional
+///
+/// This requires the optional `tower` feature enabled.
+pub struct TowerMiddleware<S> {
+    // Tower services are rarely `Sync`, we only need the lock to clone the service.
+    service: Mutex<S>,
+}
+
+impl<S> TowerMiddleware<S> {
+    /// Build the service of `layer` on top of the rest of the middleware chain.
+    pub fn new<L>(layer: L) -> Self
+    where
+        L: Layer<NextService, Service = S>,
+    {
+        Self {
+            service: Mutex::new(layer.layer(NextService)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> Middleware for TowerMiddleware<S>
+where
+    S: Service<TowerRequest, Response = Response> + Clone + Send + 'static,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    S::Future: Send,
+{
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let (forward_tx, forward_rx) = oneshot::channel::<Forward>();
+
+        let mut service = self
+            .service
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let call = async move {
+            poll_fn(|cx| service.poll_ready(cx)).await?;
+            service
+                .call(TowerRequest {
+                    request: req,
+                    next: forward_tx,
+                })
+                .await
+        };
+        let forward = async move {
+            if let Ok((request, reply)) = forward_rx.await {
+                // The receiver might have been dropped if the layer gave up on the request, in
+                // which case there's nobody left to report the result to.
+                let _ = reply.send(next.run(request, extensions).await);
+            }
+        };
+
+        futures_util::pin_mut!(call, forward);
+        let result = match future::select(call, forward).await {
+            // The layer is done (e.g. it timed out): drop the rest of the chain if still running.
+            Either::Left((result, _)) => result,
+            Either::Right(((), call)) => call.await,
+        };
+        result.map_err(|err| match err.into().downcast::<Error>() {
+            Ok(err) => *err,
+            Err(err) => Error::Middleware(anyhow::anyhow!(err)),
+        })
+    }
+}
@@ -33,7 +33,7 @@
 //!         .with(LoggingMiddleware)
 //!         .build();
 //!     let resp = client.get("https://truelayer.com").send().await.unwrap();
-//!     println!("TrueLayer page HTML: {}", resp.text().unwrap());
+//!     println!("TrueLayer page HTML: {}", resp.text().await.unwrap());
 //! }
 //! ```
 //!
@@ -50,15 +50,40 @@
 pub struct ReadmeDoctests;
 
 mod client;
+mod conditional;
 mod error;
+mod fn_middleware;
+mod hooks;
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+mod layer;
+pub mod matchers;
 mod middleware;
+mod named;
 mod req_init;
 mod response;
+mod stack;
+mod swap;
+mod transport;
+#[cfg(feature = "websocket")]
+mod websocket;
 
 pub use client::{ClientBuilder, ClientWithMiddleware, RequestBuilder};
-pub use error::{Error, Result};
-pub use middleware::{Middleware, Next};
-pub use req_init::{Extension, RequestInitialiser};
-pub use response::Response;
+pub use conditional::{Conditional, RequestMatcher};
+pub use error::{Error, Result, StatusError};
+pub use fn_middleware::{
+    from_fn, map_err, map_request, map_response, FnError, FnNext, FromFn, MapErr, MapRequest,
+    MapResponse,
+};
+pub use hooks::{OnRequest, OnResponse};
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+pub use layer::{NextService, TowerError, TowerMiddleware, TowerRequest};
+pub use middleware::{BoxFuture, Middleware, Next, SkipMiddleware};
+pub use named::Named;
+pub use req_init::{AsyncRequestInitialiser, Extension, RequestInitialiser};
+pub use response::{Response, ResponseBuilder};
 pub use rquest;
-
+pub use stack::{Identity, StaticMiddleware, StaticNext, StaticStack, Then};
+pub use swap::StackHandle;
+pub use transport::Transport;
+#[cfg(feature = "websocket")]
+pub use websocket::{WebSocketError, WebSocketRequestBuilder};
@@ -0,0 +1,192 @@
+//! Built-in [`RequestMatcher`] implementations to use with [`Conditional`](crate::Conditional).
+use http::Extensions;
+use rquest::{Method, Request};
+
+use crate::RequestMatcher;
+
+/// Matches the request host against a glob pattern.
+#[derive(Debug, Clone)]
+pub struct HostMatcher(String);
+
+/// Matches requests whose host matches the glob `pattern`, ignoring case. `*` matches any
+/// sequence of characters, e.g. `host("*.example.com")` matches `api.example.com` but not
+/// `example.com`.
+pub fn host<T: AsRef<str>>(pattern: T) -> HostMatcher {
+    HostMatcher(pattern.as_ref().to_ascii_lowercase())
+}
+
+impl RequestMatcher for HostMatcher {
+    fn matches(&self, req: &Request, _extensions: &Extensions) -> bool {
+        req.url()
+            .host_str()
+            .is_some_and(|host| glob(&self.0, &host.to_ascii_lowercase()))
+    }
+}
+
+/// Matches the start of the request path.
+#[derive(Debug, Clone)]
+pub struct PathPrefixMatcher(String);
+
+/// Matches requests whose path starts with the segments of `prefix`, e.g. `path_prefix("/api")`
+/// matches `/api` and `/api/users` but not `/apis`.
+pub fn path_prefix<T: AsRef<str>>(prefix: T) -> PathPrefixMatcher {
+    PathPrefixMatcher(prefix.as_ref().trim_end_matches('/').to_owned())
+}
+
+impl RequestMatcher for PathPrefixMatcher {
+    fn matches(&self, req: &Request, _extensions: &Extensions) -> bool {
+        match req.url().path().strip_prefix(self.0.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+}
+
+/// Matches the request method against a set of methods.
+#[derive(Debug, Clone)]
+pub struct MethodsMatcher(Vec<Method>);
+
+/// Matches requests with one of the given methods, e.g. `methods(["GET", "HEAD"])`.
+///
+/// # Panics
+///
+/// Panics if any of `methods` is not a valid HTTP method.
+pub fn methods<I, T>(methods: I) -> MethodsMatcher
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<str>,
+{
+    let methods = methods
+        .into_iter()
+        .map(|method| Method::from_bytes(method.as_ref().as_bytes()).expect("invalid HTTP method"))
+        .collect();
+    MethodsMatcher(methods)
+}
+
+impl RequestMatcher for MethodsMatcher {
+    fn matches(&self, req: &Request, _extensions: &Extensions) -> bool {
+        self.0.contains(req.method())
+    }
+}
+
+/// Matches requests matched by both matchers, see [`RequestMatcher::and`].
+#[derive(Debug, Clone)]
+pub struct And<A, B>(pub(crate) A, pub(crate) B);
+
+impl<A: RequestMatcher, B: RequestMatcher> RequestMatcher for And<A, B> {
+    fn matches(&self, req: &Request, extensions: &Extensions) -> bool {
+        self.0.matches(req, extensions) && self.1.matches(req, extensions)
+    }
+}
+
+/// Matches requests matched by either matcher, see [`RequestMatcher::or`].
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(pub(crate) A, pub(crate) B);
+
+impl<A: RequestMatcher, B: RequestMatcher> RequestMatcher for Or<A, B> {
+    fn matches(&self, req: &Request, extensions: &Extensions) -> bool {
+        self.0.matches(req, extensions) || self.1.matches(req, extensions)
+    }
+}
+
+/// Matches requests not matched by the inner matcher, see [`RequestMatcher::not`].
+#[derive(Debug, Clone)]
+pub struct Not<A>(pub(crate) A);
+
+impl<A: RequestMatcher> RequestMatcher for Not<A> {
+    fn matches(&self, req: &Request, extensions: &Extensions) -> bool {
+        !self.0.matches(req, extensions)
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any sequence of characters.
+fn glob(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern, and of the text when we reached it.
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character.
+                Some((star, start)) => {
+                    backtrack = Some((star, start + 1));
+                    p = star + 1;
+                    t = start + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: Method, url: &str) -> Request {
+        Request::new(method, url.parse().unwrap())
+    }
+
+    fn matches(matcher: impl RequestMatcher, req: &Request) -> bool {
+        matcher.matches(req, &Extensions::new())
+    }
+
+    #[test]
+    fn host_globs() {
+        let req = request(Method::GET, "https://API.example.com/users");
+        assert!(matches(host("api.example.com"), &req));
+        assert!(matches(host("*.example.com"), &req));
+        assert!(matches(host("api.*.com"), &req));
+        assert!(matches(host("*"), &req));
+        assert!(!matches(host("*.example.org"), &req));
+        assert!(!matches(
+            host("*.example.com"),
+            &request(Method::GET, "https://example.com")
+        ));
+    }
+
+    #[test]
+    fn path_prefix_matches_whole_segments() {
+        let matcher = path_prefix("/api/");
+        assert!(matches(
+            matcher.clone(),
+            &request(Method::GET, "https://example.com/api")
+        ));
+        assert!(matches(
+            matcher.clone(),
+            &request(Method::GET, "https://example.com/api/users")
+        ));
+        assert!(!matches(
+            matcher,
+            &request(Method::GET, "https://example.com/apis")
+        ));
+    }
+
+    #[test]
+    fn methods_and_combinators() {
+        let get = request(Method::GET, "https://example.com/api");
+        let post = request(Method::POST, "https://example.com/api");
+        assert!(matches(methods(["GET", "HEAD"]), &get));
+        assert!(!matches(methods(["GET", "HEAD"]), &post));
+
+        let matcher = methods(["POST"]).or(host("*.internal")).not();
+        assert!(matches(matcher.clone(), &get));
+        assert!(!matches(matcher, &post));
+        assert!(matches(
+            path_prefix("/api").and(|_: &Request, _: &Extensions| true),
+            &post
+        ));
+    }
+}
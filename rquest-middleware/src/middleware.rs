@@ -1,10 +1,13 @@
 use http::Extensions;
-use rquest::{Client, Request};
+use rquest::Request;
 
-
-use crate::error::{Error, Result};
+use crate::error::Result;
 use crate::response::Response;
+use crate::transport::Transport;
 
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::fmt;
 use std::sync::Arc;
 
 /// When attached to a [`ClientWithMiddleware`] (generally using [`with`]), middleware is run
@@ -48,6 +51,26 @@ pub trait Middleware: 'static + Send + Sync {
         extensions: &mut Extensions,
         next: Next<'_>,
     ) -> Result<Response>;
+
+    /// A name identifying the middleware, e.g. to skip it with [`SkipMiddleware::named`].
+    ///
+    /// Defaults to the name of the type implementing the trait.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Identifies the type of the middleware for [`SkipMiddleware::of`]. Wrappers forward it so
+    /// the wrapped middleware can still be skipped.
+    #[doc(hidden)]
+    fn middleware_type_id(&self) -> TypeId {
+        TypeId::of::<Self>()
+    }
+}
+
+impl fmt::Debug for dyn Middleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
@@ -69,8 +92,6 @@ where
     }
 }
 
-
-
 /// Next encapsulates the remaining middleware chain to run in [`Middleware::handle`]. You can
 /// forward the request down the chain with [`run`].
 ///
@@ -78,16 +99,19 @@ where
 /// [`run`]: Self::run
 #[derive(Clone)]
 pub struct Next<'a> {
-    client: &'a Client,
+    transport: &'a dyn Transport,
     middlewares: &'a [Arc<dyn Middleware>],
 }
 
 pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
 
 impl<'a> Next<'a> {
-    pub(crate) fn new(client: &'a Client, middlewares: &'a [Arc<dyn Middleware>]) -> Self {
+    pub(crate) fn new(
+        transport: &'a dyn Transport,
+        middlewares: &'a [Arc<dyn Middleware>],
+    ) -> Self {
         Next {
-            client,
+            transport,
             middlewares,
         }
     }
@@ -97,15 +121,90 @@ impl<'a> Next<'a> {
         req: Request,
         extensions: &'a mut Extensions,
     ) -> BoxFuture<'a, Result<Response>> {
-        if let Some((current, rest)) = self.middlewares.split_first() {
+        while let Some((current, rest)) = self.middlewares.split_first() {
             self.middlewares = rest;
-            current.handle(req, extensions, self)
-        } else {
-            Box::pin(async move { 
-    
-                let resp = self.client.execute(req).await.map_err(Error::from)?;
-                Ok(Response::from_original(resp).await?) 
-            })
+            let skipped = extensions
+                .get::<SkipMiddleware>()
+                .is_some_and(|skip| skip.skips(&**current));
+            if !skipped {
+                return current.handle(req, extensions, self);
+            }
         }
+        self.transport.execute(req, extensions)
     }
-}
\ No newline at end of file
+}
+
+/// A marker to put in the request [`Extensions`] to skip some middleware for this request only.
+///
+/// Middleware are identified by type, or by [`Middleware::name`]. Only one `SkipMiddleware` is
+/// kept in the extensions, list every middleware to skip in it.
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::{ClientBuilder, SkipMiddleware};
+/// # use rquest_middleware::{Middleware, Next, Response, Result};
+/// # struct CacheMiddleware;
+/// # #[async_trait::async_trait]
+/// # impl Middleware for CacheMiddleware {
+/// #     async fn handle(
+/// #         &self,
+/// #         req: rquest::Request,
+/// #         extensions: &mut http::Extensions,
+/// #         next: Next<'_>,
+/// #     ) -> Result<Response> {
+/// #         next.run(req, extensions).await
+/// #     }
+/// # }
+///
+/// async fn run() {
+///     let client = ClientBuilder::new(rquest::Client::new())
+///         .with(CacheMiddleware)
+///         .build();
+///     let resp = client
+///         .get("https://truelayer.com")
+///         .with_extension(SkipMiddleware::of::<CacheMiddleware>())
+///         .send()
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SkipMiddleware {
+    types: Vec<TypeId>,
+    names: Vec<Cow<'static, str>>,
+}
+
+impl SkipMiddleware {
+    /// Creates a marker which doesn't skip any middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skips middleware of type `M`.
+    pub fn of<M: Middleware>() -> Self {
+        Self::new().and::<M>()
+    }
+
+    /// Skips middleware whose [`Middleware::name`] is `name`.
+    pub fn named<N: Into<Cow<'static, str>>>(name: N) -> Self {
+        Self::new().and_named(name)
+    }
+
+    /// Also skips middleware of type `M`.
+    pub fn and<M: Middleware>(mut self) -> Self {
+        self.types.push(TypeId::of::<M>());
+        self
+    }
+
+    /// Also skips middleware whose [`Middleware::name`] is `name`.
+    pub fn and_named<N: Into<Cow<'static, str>>>(mut self, name: N) -> Self {
+        self.names.push(name.into());
+        self
+    }
+
+    fn skips(&self, middleware: &dyn Middleware) -> bool {
+        self.types.contains(&middleware.middleware_type_id())
+            || self.names.iter().any(|name| name == middleware.name())
+    }
+}
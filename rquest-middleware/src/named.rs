@@ -0,0 +1,102 @@
+use std::any::TypeId;
+use std::borrow::Cow;
+
+use http::Extensions;
+use rquest::Request;
+
+use crate::error::Result;
+use crate::middleware::{Middleware, Next};
+use crate::req_init::{AsyncRequestInitialiser, RequestInitialiser};
+use crate::response::Response;
+use crate::RequestBuilder;
+
+/// Gives a name to a middleware or initialiser, overriding [`Middleware::name`] or
+/// [`RequestInitialiser::name`].
+///
+/// The wrapped middleware can still be skipped by type with
+/// [`SkipMiddleware::of`](crate::SkipMiddleware::of).
+///
+/// # Example
+///
+/// ```
+/// use rquest_middleware::{ClientBuilder, Extension, Named};
+///
+/// #[derive(Clone)]
+/// struct LogName(&'static str);
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with_init(Named::new("log-name", Extension(LogName("my-client"))))
+///     .build();
+///
+/// assert_eq!(
+///     client.initialisers().map(|i| i.name()).collect::<Vec<_>>(),
+///     ["log-name"]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Named<T> {
+    name: Cow<'static, str>,
+    inner: T,
+}
+
+impl<T> Named<T> {
+    /// Wraps `inner` under `name`.
+    pub fn new<N: Into<Cow<'static, str>>>(name: N, inner: T) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+        }
+    }
+
+    /// Returns a reference to the wrapped middleware or initialiser.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<M: Middleware> Middleware for Named<M> {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        self.inner.handle(req, extensions, next).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn middleware_type_id(&self) -> TypeId {
+        self.inner.middleware_type_id()
+    }
+}
+
+impl<I: RequestInitialiser> RequestInitialiser for Named<I> {
+    fn init(&self, req: RequestBuilder) -> RequestBuilder {
+        self.inner.init(req)
+    }
+
+    fn init_request(&self, req: &mut Request, extensions: &mut Extensions) {
+        self.inner.init_request(req, extensions)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<I: AsyncRequestInitialiser> AsyncRequestInitialiser for Named<I> {
+    async fn init(&self, req: &mut Request, extensions: &mut Extensions) -> Result<()> {
+        self.inner.init(req, extensions).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -1,8 +1,18 @@
-use crate::RequestBuilder;
+use std::fmt;
+
+use http::Extensions;
+use rquest::Request;
+
+use crate::{RequestBuilder, Result};
 
 /// When attached to a [`ClientWithMiddleware`] (generally using [`with_init`]), it is run
 /// whenever the client starts building a request, in the order it was attached.
 ///
+/// [`init`](Self::init) only runs for requests built with the client's [`RequestBuilder`].
+/// [`init_request`](Self::init_request) runs on every execution path, right before the request
+/// enters the middleware chain: [`RequestBuilder::send`], [`execute`], [`execute_with_extensions`]
+/// and the `tower_service::Service` implementations of the client.
+///
 /// # Example
 ///
 /// ```
@@ -19,8 +29,31 @@ use crate::RequestBuilder;
 ///
 /// [`ClientWithMiddleware`]: crate::ClientWithMiddleware
 /// [`with_init`]: crate::ClientBuilder::with_init
+/// [`execute`]: crate::ClientWithMiddleware::execute
+/// [`execute_with_extensions`]: crate::ClientWithMiddleware::execute_with_extensions
 pub trait RequestInitialiser: 'static + Send + Sync {
     fn init(&self, req: RequestBuilder) -> RequestBuilder;
+
+    /// Initialise an already built request. Requests built with a [`RequestBuilder`] went
+    /// through [`init`](Self::init) first, so this should not blindly add the same data again.
+    ///
+    /// The default implementation does nothing.
+    fn init_request(&self, req: &mut Request, extensions: &mut Extensions) {
+        let _ = (req, extensions);
+    }
+
+    /// A name identifying the initialiser, e.g. in the `Debug` output of the client.
+    ///
+    /// Defaults to the name of the type implementing the trait.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl fmt::Debug for dyn RequestInitialiser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl<F> RequestInitialiser for F
@@ -32,6 +65,62 @@ where
     }
 }
 
+/// An initialiser which can perform I/O, e.g. to fetch a token from a secret store.
+///
+/// When attached to a [`ClientWithMiddleware`] (generally using [`with_async_init`]), it is run
+/// when a request is sent, after every [`RequestInitialiser`] and before the request enters the
+/// middleware chain, in the order it was attached. Like
+/// [`RequestInitialiser::init_request`], it runs on every execution path.
+///
+/// An error aborts the request before any middleware runs.
+///
+/// # Example
+///
+/// ```
+/// use http::Extensions;
+/// use rquest::header::{HeaderValue, AUTHORIZATION};
+/// use rquest::Request;
+/// use rquest_middleware::{AsyncRequestInitialiser, Error, Result};
+///
+/// struct TokenInit;
+///
+/// async fn fetch_token() -> std::io::Result<String> {
+///     Ok("my_auth_token".to_owned())
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl AsyncRequestInitialiser for TokenInit {
+///     async fn init(&self, req: &mut Request, _extensions: &mut Extensions) -> Result<()> {
+///         let token = fetch_token().await.map_err(Error::middleware)?;
+///         let value =
+///             HeaderValue::from_str(&format!("Bearer {token}")).map_err(Error::middleware)?;
+///         req.headers_mut().insert(AUTHORIZATION, value);
+///         Ok(())
+///     }
+/// }
+/// ```
+///
+/// [`ClientWithMiddleware`]: crate::ClientWithMiddleware
+/// [`with_async_init`]: crate::ClientBuilder::with_async_init
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait AsyncRequestInitialiser: 'static + Send + Sync {
+    async fn init(&self, req: &mut Request, extensions: &mut Extensions) -> Result<()>;
+
+    /// A name identifying the initialiser, e.g. in the `Debug` output of the client.
+    ///
+    /// Defaults to the name of the type implementing the trait.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl fmt::Debug for dyn AsyncRequestInitialiser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// A middleware that inserts the value into the [`Extensions`](http::Extensions) during the call.
 ///
 /// This is a good way to inject extensions to middleware deeper in the stack
@@ -72,7 +161,7 @@ where
 ///         .with(LoggingMiddleware)
 ///         .build();
 ///     let resp = client.get("https://truelayer.com").send().await.unwrap();
-///     println!("TrueLayer page HTML: {}", resp.text().unwrap());
+///     println!("TrueLayer page HTML: {}", resp.text().await.unwrap());
 /// }
 /// ```
 pub struct Extension<T>(pub T);
@@ -81,4 +170,12 @@ impl<T: Send + Sync + Clone + 'static> RequestInitialiser for Extension<T> {
     fn init(&self, req: RequestBuilder) -> RequestBuilder {
         req.with_extension(self.0.clone())
     }
+
+    /// Inserts the value unless the request already carries one, e.g. from [`init`](Self::init)
+    /// or [`RequestBuilder::with_extension`].
+    fn init_request(&self, _req: &mut Request, extensions: &mut Extensions) {
+        if extensions.get::<T>().is_none() {
+            extensions.insert(self.0.clone());
+        }
+    }
 }
@@ -1,35 +1,164 @@
 use bytes::Bytes;
 use http::Extensions;
-use rquest::Url;
+use rquest::header::{HeaderName, HeaderValue};
+use rquest::{StatusCode, TlsInfo, Url, Version};
+use std::convert::TryFrom;
+use std::net::SocketAddr;
 
-#[derive(Debug, Clone)]
+use crate::error::StatusError;
+
+/// The `Url` given to responses which were not created from a request, e.g. when converting
+/// from an [`http::Response`] which doesn't carry one.
+const NO_URL_PROVIDED: &str = "http://no.url.provided.local";
+
+/// The address of the remote peer, stored in the extensions so it survives conversions to and
+/// from [`http::Response`].
+#[derive(Clone, Copy, Debug)]
+struct RemoteAddr(SocketAddr);
+
+#[cfg(not(feature = "charset"))]
+const UTF_8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// A response to a request sent through a [`ClientWithMiddleware`].
+///
+/// The body is not read when the response comes back from the server: middleware can inspect the
+/// status and headers while the body stays lazy. Use [`Response::buffer`] to explicitly load the
+/// body into memory, e.g. if middleware needs to look at it or the response has to be cloned.
+///
+/// [`ClientWithMiddleware`]: crate::ClientWithMiddleware
+#[derive(Debug)]
 pub struct Response {
-    response_body: Bytes,
+    body: ResponseBody,
     parts: http::response::Parts,
     url: rquest::Url,
 }
 
+#[derive(Debug)]
+enum ResponseBody {
+    /// The body has been fully read into memory.
+    Buffered(Bytes),
+    /// The body has not been fully read yet and the rest is still owned by the original response.
+    /// `read` is the start of the body when it was already read by [`Response::buffer_up_to`].
+    Streaming {
+        read: Bytes,
+        inner: rquest::Response,
+    },
+}
+
 impl Response {
-    pub(crate) async fn from_original(inner: rquest::Response) -> crate::Result<Self> {
+    /// Creates a new [`ResponseBuilder`] to build a `Response` without sending a request.
+    ///
+    /// This is useful for middleware that needs to answer a request on its own, e.g. caching
+    /// or mocking middleware.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rquest_middleware::Response;
+    ///
+    /// let res = Response::builder()
+    ///     .status(404)
+    ///     .header("content-type", "text/plain")
+    ///     .url("https://example.com/foo".parse().unwrap())
+    ///     .body("not found")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(res.status(), 404);
+    /// assert_eq!(res.url().as_str(), "https://example.com/foo");
+    /// ```
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::new()
+    }
+
+    pub(crate) fn from_original(inner: rquest::Response) -> Self {
         let response = http::response::Response::new(());
         let (mut parts, ()) = response.into_parts();
 
         parts.status = inner.status();
         parts.headers = inner.headers().clone();
         parts.version = inner.version();
-        parts.extensions = Extensions::default();
+        parts.extensions = inner.extensions().clone();
+        if let Some(addr) = inner.remote_addr() {
+            parts.extensions.insert(RemoteAddr(addr));
+        }
 
         let url = inner.url().clone();
 
-        let preloaded_bytes = inner
-            .bytes()
-            .await
-            .map_err(|e| crate::error::Error::Rquest(e.into()))?;
-
-        Ok(Self {
-            response_body: preloaded_bytes,
+        Self {
+            body: ResponseBody::Streaming {
+                read: Bytes::new(),
+                inner,
+            },
             parts,
             url,
+        }
+    }
+
+    /// Read the whole body into memory.
+    ///
+    /// This is a no-op if the body has already been buffered. Once buffered, the body can be
+    /// inspected with [`Response::buffered_bytes`] and the response can be cloned with
+    /// [`Response::try_clone`].
+    pub async fn buffer(mut self) -> crate::Result<Self> {
+        if let ResponseBody::Streaming { read, inner } = self.body {
+            self.body = ResponseBody::Buffered(read_to_end(read, inner).await?);
+        }
+        Ok(self)
+    }
+
+    /// Read the whole body into memory, unless it is longer than `limit` bytes.
+    ///
+    /// The body is left streaming if it turns out to be longer than `limit`, either from its
+    /// `Content-Length` or once `limit` bytes were read. The part already read is not lost: it
+    /// comes first when the body is read later on. Check [`Response::is_buffered`] to know which
+    /// case happened.
+    pub async fn buffer_up_to(mut self, limit: usize) -> crate::Result<Self> {
+        let (read, mut inner) = match self.body {
+            ResponseBody::Streaming { read, inner } => (read, inner),
+            ResponseBody::Buffered(_) => return Ok(self),
+        };
+        if inner.content_length().is_some_and(|len| len > limit as u64) {
+            self.body = ResponseBody::Streaming { read, inner };
+            return Ok(self);
+        }
+
+        let mut buf = read.to_vec();
+        self.body = loop {
+            if buf.len() > limit {
+                break ResponseBody::Streaming {
+                    read: buf.into(),
+                    inner,
+                };
+            }
+            match inner.chunk().await? {
+                Some(chunk) => buf.extend_from_slice(&chunk),
+                None => break ResponseBody::Buffered(buf.into()),
+            }
+        };
+        Ok(self)
+    }
+
+    /// Returns `true` if the body has been read into memory.
+    pub fn is_buffered(&self) -> bool {
+        matches!(self.body, ResponseBody::Buffered(_))
+    }
+
+    /// Returns the body if it has been read into memory, `None` if it is still streaming.
+    pub fn buffered_bytes(&self) -> Option<&Bytes> {
+        match &self.body {
+            ResponseBody::Buffered(bytes) => Some(bytes),
+            ResponseBody::Streaming { .. } => None,
+        }
+    }
+
+    /// Attempt to clone the `Response`.
+    ///
+    /// `None` is returned if the body has not been buffered, see [`Response::buffer`].
+    pub fn try_clone(&self) -> Option<Self> {
+        self.buffered_bytes().map(|bytes| Self {
+            body: ResponseBody::Buffered(bytes.clone()),
+            parts: self.parts.clone(),
+            url: self.url.clone(),
         })
     }
 
@@ -76,12 +205,38 @@ impl Response {
             .and_then(|value| value.parse::<u64>().ok())
     }
 
+    /// Get the remote address used to get this `Response`.
+    ///
+    /// `None` if the response was not received from the network, e.g. built with
+    /// [`Response::builder`] without setting [`ResponseBuilder::remote_addr`].
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.parts
+            .extensions
+            .get::<RemoteAddr>()
+            .map(|&RemoteAddr(addr)| addr)
+    }
+
+    /// Get the TLS details of the connection used to get this `Response`, such as the peer
+    /// certificate.
+    ///
+    /// `None` unless the request was sent over TLS with a client built with
+    /// `rquest::ClientBuilder::tls_info(true)`.
+    pub fn tls_info(&self) -> Option<&TlsInfo> {
+        self.parts.extensions.get::<TlsInfo>()
+    }
+
     /// Get the final `Url` of this `Response`.
     #[inline]
     pub fn url(&self) -> &Url {
         &self.url
     }
 
+    /// Get a mutable reference to the `Url` of this `Response`.
+    #[inline]
+    pub fn url_mut(&mut self) -> &mut Url {
+        &mut self.url
+    }
+
     /// Returns a reference to the associated extensions.
     pub fn extensions(&self) -> &http::Extensions {
         &self.parts.extensions
@@ -112,7 +267,7 @@ impl Response {
     ///
     /// ```
     /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    /// let content = rquest::Client::new()
+    /// let content = rquest_middleware::ClientWithMiddleware::from(rquest::Client::new())
     ///     .get("http://httpbin.org/range/26")
     ///     .send()
     ///     .await?
@@ -123,9 +278,16 @@ impl Response {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn text(self) -> crate::Result<String> {
-        String::from_utf8(self.response_body.to_vec())
-            .map_err(|e| crate::error::Error::Middleware(anyhow::Error::from(e)))
+    pub async fn text(self) -> crate::Result<String> {
+        #[cfg(feature = "charset")]
+        let text = self.text_with_charset("utf-8").await?;
+        #[cfg(not(feature = "charset"))]
+        let text = {
+            let full = self.bytes().await?;
+            let full = full.strip_prefix(UTF_8_BOM).unwrap_or(&full);
+            String::from_utf8_lossy(full).into_owned()
+        };
+        Ok(text)
     }
 
     /// Get the full response text given a specific encoding.
@@ -143,13 +305,13 @@ impl Response {
     ///
     /// # Optional
     ///
-    /// This requires the optional `encoding_rs` feature enabled.
+    /// This requires the optional `charset` feature enabled.
     ///
     /// # Example
     ///
     /// ```
     /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    /// let content = rquest::Client::new()
+    /// let content = rquest_middleware::ClientWithMiddleware::from(rquest::Client::new())
     ///     .get("http://httpbin.org/range/26")
     ///     .send()
     ///     .await?
@@ -165,14 +327,15 @@ impl Response {
     pub async fn text_with_charset(self, default_encoding: &str) -> crate::Result<String> {
         let content_type = self
             .headers()
-            .get(crate::header::CONTENT_TYPE)
+            .get(rquest::header::CONTENT_TYPE)
             .and_then(|value| value.to_str().ok())
-            .and_then(|value| value.parse::<Mime>().ok());
+            .and_then(|value| value.parse::<mime::Mime>().ok());
         let encoding_name = content_type
             .as_ref()
             .and_then(|mime| mime.get_param("charset").map(|charset| charset.as_str()))
             .unwrap_or(default_encoding);
-        let encoding = Encoding::for_label(encoding_name.as_bytes()).unwrap_or(UTF_8);
+        let encoding = encoding_rs::Encoding::for_label(encoding_name.as_bytes())
+            .unwrap_or(encoding_rs::UTF_8);
 
         let full = self.bytes().await?;
 
@@ -225,9 +388,9 @@ impl Response {
     /// [`serde_json::from_reader`]: https://docs.serde.rs/serde_json/fn.from_reader.html
     #[cfg(feature = "json")]
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
-    pub fn json<T: serde::de::DeserializeOwned>(self) -> crate::Result<T> {
-        serde_json::from_slice(self.response_body.as_ref())
-            .map_err(|e| crate::error::Error::Rquest(e.into()))
+    pub async fn json<T: serde::de::DeserializeOwned>(self) -> crate::Result<T> {
+        let full = self.bytes().await?;
+        serde_json::from_slice(&full).map_err(|e| crate::error::Error::Rquest(e.into()))
     }
 
     /// Get the full response body as `Bytes`.
@@ -248,19 +411,93 @@ impl Response {
     /// # }
     /// ```
     pub async fn bytes(self) -> crate::Result<Bytes> {
-        Ok(self.response_body)
+        match self.body {
+            ResponseBody::Buffered(bytes) => Ok(bytes),
+            ResponseBody::Streaming { read, inner } => read_to_end(read, inner).await,
+        }
+    }
+
+    /// Stream a chunk of the response body.
+    ///
+    /// When the response body has been exhausted, this will return `None`. A buffered body is
+    /// returned as a single chunk.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = rquest_middleware::ClientWithMiddleware::from(rquest::Client::new());
+    /// let mut res = client.get("https://hyper.rs").send().await?;
+    ///
+    /// while let Some(chunk) = res.chunk().await? {
+    ///     println!("Chunk: {chunk:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn chunk(&mut self) -> crate::Result<Option<Bytes>> {
+        match &mut self.body {
+            ResponseBody::Buffered(bytes) if bytes.is_empty() => Ok(None),
+            ResponseBody::Buffered(bytes) => Ok(Some(std::mem::take(bytes))),
+            ResponseBody::Streaming { read, .. } if !read.is_empty() => {
+                Ok(Some(std::mem::take(read)))
+            }
+            ResponseBody::Streaming { inner, .. } => Ok(inner.chunk().await?),
+        }
+    }
+
+    /// Convert the response into a `Stream` of `Bytes` from the body.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = rquest_middleware::ClientWithMiddleware::from(rquest::Client::new());
+    /// let mut stream = client.get("http://httpbin.org/ip").send().await?.bytes_stream();
+    ///
+    /// while let Some(item) = stream.next().await {
+    ///     println!("Chunk: {:?}", item?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn bytes_stream(
+        self,
+    ) -> impl futures_util::Stream<Item = crate::Result<Bytes>> + Send + 'static {
+        use futures_util::{future::Either, stream, StreamExt, TryStreamExt};
+
+        match self.body {
+            ResponseBody::Buffered(bytes) => Either::Left(stream::once(async move { Ok(bytes) })),
+            ResponseBody::Streaming { read, inner } => {
+                let read = Some(read).filter(|read| !read.is_empty()).map(Ok);
+                Either::Right(
+                    stream::iter(read).chain(inner.bytes_stream().map_err(crate::Error::from)),
+                )
+            }
+        }
     }
 
     // util methods
 
     /// Turn a response into an error if the server returned an error.
     ///
+    /// The returned [`Error::Status`] carries the status, headers, url and body of the response.
+    /// The body is read into memory to do so, if reading it fails the error carries an empty body.
+    ///
     /// # Example
     ///
     /// ```
-    /// # use rquest::Response;
-    /// fn on_response(res: Response) {
-    ///     match res.error_for_status() {
+    /// # use rquest_middleware::Response;
+    /// async fn on_response(res: Response) {
+    ///     match res.error_for_status().await {
     ///         Ok(_res) => (),
     ///         Err(err) => {
     ///             // asserting a 400 as an example
@@ -274,14 +511,283 @@ impl Response {
     /// }
     /// # fn main() {}
     /// ```
-    pub fn error_for_status(self) -> crate::Result<Self> {
-        if self.status().is_client_error() || self.status().is_server_error() {
-            Err(crate::Error::Middleware(anyhow::Error::msg(format!(
-                "Response error: {}",
-                self.status()
+    ///
+    /// [`Error::Status`]: crate::Error::Status
+    pub async fn error_for_status(self) -> crate::Result<Self> {
+        let status = self.status();
+        if status.is_client_error() || status.is_server_error() {
+            let headers = self.parts.headers.clone();
+            let url = self.url.clone();
+            let body = self.bytes().await.unwrap_or_default();
+            Err(crate::Error::Status(Box::new(StatusError::new(
+                status, headers, url, body,
             ))))
         } else {
             Ok(self)
         }
     }
 }
+
+/// Reads the rest of a body whose start is `read`.
+async fn read_to_end(read: Bytes, inner: rquest::Response) -> crate::Result<Bytes> {
+    let rest = inner.bytes().await?;
+    if read.is_empty() {
+        return Ok(rest);
+    }
+    let mut body = read.to_vec();
+    body.extend_from_slice(&rest);
+    Ok(body.into())
+}
+
+/// Converts an [`http::Response`] into a `Response`.
+///
+/// If the response extensions contain a [`Url`] it is used as the response's url, otherwise a
+/// placeholder url is used.
+impl From<http::Response<Bytes>> for Response {
+    fn from(response: http::Response<Bytes>) -> Self {
+        let (mut parts, body) = response.into_parts();
+        let url = parts.extensions.remove::<Url>().unwrap_or_else(|| {
+            Url::parse(NO_URL_PROVIDED).expect("placeholder url should always parse")
+        });
+
+        Self {
+            body: ResponseBody::Buffered(body),
+            parts,
+            url,
+        }
+    }
+}
+
+/// Converts a buffered `Response` into an [`http::Response`], storing its url in the extensions.
+///
+/// This fails, giving back the original response, if the body has not been buffered yet. Use
+/// [`Response::buffer`] first if that's the case.
+impl TryFrom<Response> for http::Response<Bytes> {
+    type Error = Response;
+
+    fn try_from(response: Response) -> Result<Self, Self::Error> {
+        match response.body {
+            ResponseBody::Buffered(body) => {
+                let Response { mut parts, url, .. } = response;
+                parts.extensions.insert(url);
+                Ok(http::Response::from_parts(parts, body))
+            }
+            ResponseBody::Streaming { .. } => Err(response),
+        }
+    }
+}
+
+/// A builder for a [`Response`], created with [`Response::builder`].
+#[derive(Debug)]
+#[must_use = "ResponseBuilder does nothing until you set its 'body'"]
+pub struct ResponseBuilder {
+    inner: http::response::Builder,
+    url: Option<Url>,
+}
+
+impl Default for ResponseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResponseBuilder {
+    /// Creates a builder for a `200 OK` response without headers.
+    pub fn new() -> Self {
+        Self {
+            inner: http::response::Builder::new(),
+            url: None,
+        }
+    }
+
+    /// Set the `StatusCode` of the response.
+    pub fn status<T>(self, status: T) -> Self
+    where
+        StatusCode: TryFrom<T>,
+        <StatusCode as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        Self {
+            inner: self.inner.status(status),
+            ..self
+        }
+    }
+
+    /// Set the HTTP `Version` of the response.
+    pub fn version(self, version: Version) -> Self {
+        Self {
+            inner: self.inner.version(version),
+            ..self
+        }
+    }
+
+    /// Add a `Header` to the response.
+    pub fn header<K, V>(self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        Self {
+            inner: self.inner.header(key, value),
+            ..self
+        }
+    }
+
+    /// Set the `Url` of the response. A placeholder url is used if this isn't called.
+    pub fn url(self, url: Url) -> Self {
+        Self {
+            url: Some(url),
+            ..self
+        }
+    }
+
+    /// Set the remote address the response was received from, see [`Response::remote_addr`].
+    pub fn remote_addr(self, addr: SocketAddr) -> Self {
+        self.extension(RemoteAddr(addr))
+    }
+
+    /// Inserts the extension into the response.
+    pub fn extension<T: Send + Sync + Clone + 'static>(self, extension: T) -> Self {
+        Self {
+            inner: self.inner.extension(extension),
+            ..self
+        }
+    }
+
+    /// Returns a mutable reference to the extensions of the response, `None` if the builder
+    /// already has an error.
+    pub fn extensions_mut(&mut self) -> Option<&mut Extensions> {
+        self.inner.extensions_mut()
+    }
+
+    /// Consumes the builder and sets the body, returning the finished `Response`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if any of the previously given status or headers were invalid.
+    pub fn body<B: Into<Bytes>>(self, body: B) -> crate::Result<Response> {
+        let response = self
+            .inner
+            .body(body.into())
+            .map_err(crate::Error::middleware)?;
+        let mut response = Response::from(response);
+        if let Some(url) = self.url {
+            response.url = url;
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn text_of(content_type: Option<&str>, body: &'static [u8]) -> String {
+        let mut builder = Response::builder();
+        if let Some(content_type) = content_type {
+            builder = builder.header(rquest::header::CONTENT_TYPE, content_type);
+        }
+        builder.body(body).unwrap().text().await.unwrap()
+    }
+
+    #[test]
+    fn builder_round_trips_through_http() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Tag(&'static str);
+
+        let url: Url = "https://example.com/data".parse().unwrap();
+        let res = Response::builder()
+            .status(201)
+            .version(Version::HTTP_2)
+            .header("x-name", "value")
+            .url(url.clone())
+            .extension(Tag("tag"))
+            .body("data")
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.version(), Version::HTTP_2);
+        assert_eq!(res.url(), &url);
+
+        let res = http::Response::<Bytes>::try_from(res).unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()["x-name"], "value");
+        assert_eq!(res.extensions().get::<Url>(), Some(&url));
+        assert_eq!(res.body().as_ref(), b"data");
+
+        let res = Response::from(res);
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.version(), Version::HTTP_2);
+        assert_eq!(res.headers()["x-name"], "value");
+        assert_eq!(res.url(), &url);
+        assert_eq!(res.extensions().get::<Tag>(), Some(&Tag("tag")));
+        assert!(res.extensions().get::<Url>().is_none());
+        assert_eq!(res.buffered_bytes().unwrap().as_ref(), b"data");
+    }
+
+    #[test]
+    fn responses_without_url_get_a_placeholder() {
+        let res = Response::from(http::Response::new(Bytes::new()));
+        assert_eq!(res.url().as_str(), "http://no.url.provided.local/");
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = Response::builder().body("").unwrap();
+        assert_eq!(res.url().as_str(), "http://no.url.provided.local/");
+    }
+
+    #[test]
+    fn builder_fails_on_invalid_parts() {
+        assert!(Response::builder().status(1000).body("").is_err());
+        assert!(Response::builder()
+            .header("invalid header", "value")
+            .body("")
+            .is_err());
+    }
+
+    #[test]
+    fn remote_addr_survives_http_conversions() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
+        let res = Response::builder().remote_addr(addr).body("").unwrap();
+        assert_eq!(res.remote_addr(), Some(addr));
+
+        let res = http::Response::<Bytes>::try_from(res).unwrap();
+        assert_eq!(Response::from(res).remote_addr(), Some(addr));
+        assert_eq!(Response::builder().body("").unwrap().remote_addr(), None);
+    }
+
+    #[tokio::test]
+    async fn text_strips_utf8_bom() {
+        assert_eq!(text_of(None, b"\xEF\xBB\xBFhello").await, "hello");
+    }
+
+    #[tokio::test]
+    async fn text_replaces_malformed_sequences() {
+        assert_eq!(text_of(None, b"hel\xFFlo").await, "hel\u{FFFD}lo");
+    }
+
+    #[cfg(feature = "charset")]
+    #[tokio::test]
+    async fn text_uses_content_type_charset() {
+        let text = text_of(Some("text/plain; charset=iso-8859-1"), b"caf\xE9").await;
+        assert_eq!(text, "caf\u{e9}");
+    }
+
+    #[cfg(feature = "charset")]
+    #[tokio::test]
+    async fn text_prefers_bom_over_content_type() {
+        let text = text_of(
+            Some("text/plain; charset=iso-8859-1"),
+            b"\xEF\xBB\xBFcaf\xC3\xA9",
+        )
+        .await;
+        assert_eq!(text, "caf\u{e9}");
+    }
+
+    #[cfg(feature = "charset")]
+    #[tokio::test]
+    async fn text_with_charset_falls_back_to_default() {
+        let res = Response::builder().body(&b"caf\xE9"[..]).unwrap();
+        let text = res.text_with_charset("windows-1252").await.unwrap();
+        assert_eq!(text, "caf\u{e9}");
+    }
+}
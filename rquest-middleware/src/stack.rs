@@ -0,0 +1,210 @@
+use std::future::Future;
+
+use http::Extensions;
+use rquest::Request;
+
+use crate::error::Result;
+use crate::middleware::{Middleware, Next};
+use crate::response::Response;
+
+/// Middleware which can be chained in a [`StaticStack`], known at compile time.
+///
+/// Unlike [`Middleware::handle`], the future returned by [`handle`](Self::handle) is not boxed
+/// and `next` is statically typed: a `StaticStack` is monomorphised into a single future, without
+/// an allocation for each middleware it goes through.
+///
+/// # Example
+///
+/// ```
+/// use http::Extensions;
+/// use rquest::Request;
+/// use rquest_middleware::{Response, Result, StaticMiddleware, StaticNext};
+///
+/// struct LoggingMiddleware;
+///
+/// impl StaticMiddleware for LoggingMiddleware {
+///     async fn handle<N: StaticNext>(
+///         &self,
+///         req: Request,
+///         extensions: &mut Extensions,
+///         next: N,
+///     ) -> Result<Response> {
+///         println!("Request started {req:?}");
+///         let res = next.run(req, extensions).await;
+///         println!("Result: {res:?}");
+///         res
+///     }
+/// }
+/// ```
+pub trait StaticMiddleware: 'static + Send + Sync {
+    /// Invoked with a request before sending it. If you want to continue processing the request,
+    /// you should explicitly call `next.run(req, extensions)`.
+    fn handle<N: StaticNext>(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: N,
+    ) -> impl Future<Output = Result<Response>> + Send;
+}
+
+/// The rest of the chain given to [`StaticMiddleware::handle`]: either the next middleware of
+/// the [`StaticStack`], or the [`Next`] middleware of the client once the stack is done.
+pub trait StaticNext: Send {
+    /// Forwards the request down the rest of the chain.
+    fn run(
+        self,
+        req: Request,
+        extensions: &mut Extensions,
+    ) -> impl Future<Output = Result<Response>> + Send;
+}
+
+impl StaticNext for Next<'_> {
+    fn run(
+        self,
+        req: Request,
+        extensions: &mut Extensions,
+    ) -> impl Future<Output = Result<Response>> + Send {
+        // `Next::run` ties `extensions` to the lifetime of `Next`, so its future only lives for
+        // the shorter of both. Wrapping it keeps both lifetimes in the returned type.
+        async move { Next::run(self, req, extensions).await }
+    }
+}
+
+/// Runs `middleware` with `next` as the rest of the chain.
+struct Chained<'a, M, N> {
+    middleware: &'a M,
+    next: N,
+}
+
+impl<M, N> StaticNext for Chained<'_, M, N>
+where
+    M: StaticMiddleware,
+    N: StaticNext,
+{
+    fn run(
+        self,
+        req: Request,
+        extensions: &mut Extensions,
+    ) -> impl Future<Output = Result<Response>> + Send {
+        self.middleware.handle(req, extensions, self.next)
+    }
+}
+
+/// The empty [`StaticStack`], forwarding requests as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl StaticMiddleware for Identity {
+    fn handle<N: StaticNext>(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: N,
+    ) -> impl Future<Output = Result<Response>> + Send {
+        next.run(req, extensions)
+    }
+}
+
+/// Runs `A`, then `B`, see [`StaticStack::with`].
+#[derive(Debug, Clone)]
+pub struct Then<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> StaticMiddleware for Then<A, B>
+where
+    A: StaticMiddleware,
+    B: StaticMiddleware,
+{
+    fn handle<N: StaticNext>(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: N,
+    ) -> impl Future<Output = Result<Response>> + Send {
+        let next = Chained {
+            middleware: &self.second,
+            next,
+        };
+        self.first.handle(req, extensions, next)
+    }
+}
+
+/// A stack of [`StaticMiddleware`], typed at compile time.
+///
+/// The whole stack is a single [`Middleware`], so it can be attached to a [`ClientBuilder`] like
+/// any other, mixing with dynamically dispatched middleware. Going through the stack costs one
+/// allocation, however many middleware it holds, instead of one per middleware.
+///
+/// # Example
+///
+/// ```
+/// use http::Extensions;
+/// use rquest::Request;
+/// use rquest_middleware::{
+///     ClientBuilder, Response, Result, StaticMiddleware, StaticNext, StaticStack,
+/// };
+///
+/// struct Passthrough;
+///
+/// impl StaticMiddleware for Passthrough {
+///     async fn handle<N: StaticNext>(
+///         &self,
+///         req: Request,
+///         extensions: &mut Extensions,
+///         next: N,
+///     ) -> Result<Response> {
+///         next.run(req, extensions).await
+///     }
+/// }
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(StaticStack::new().with(Passthrough).with(Passthrough))
+///     .build();
+/// ```
+///
+/// [`ClientBuilder`]: crate::ClientBuilder
+#[derive(Debug, Clone, Default)]
+pub struct StaticStack<T = Identity>(T);
+
+impl StaticStack {
+    /// Creates an empty stack.
+    pub fn new() -> Self {
+        Self(Identity)
+    }
+}
+
+impl<T: StaticMiddleware> StaticStack<T> {
+    /// Adds middleware at the end of the stack.
+    pub fn with<M: StaticMiddleware>(self, middleware: M) -> StaticStack<Then<T, M>> {
+        StaticStack(Then {
+            first: self.0,
+            second: middleware,
+        })
+    }
+}
+
+impl<T: StaticMiddleware> StaticMiddleware for StaticStack<T> {
+    fn handle<N: StaticNext>(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: N,
+    ) -> impl Future<Output = Result<Response>> + Send {
+        self.0.handle(req, extensions, next)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<T: StaticMiddleware> Middleware for StaticStack<T> {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        StaticMiddleware::handle(&self.0, req, extensions, next).await
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use crate::middleware::Middleware;
+
+type Stack = Vec<Arc<dyn Middleware>>;
+
+/// The middleware of a [`ClientWithMiddleware`](crate::ClientWithMiddleware), either frozen at
+/// `build()` or replaceable through a [`StackHandle`].
+#[derive(Clone)]
+pub(crate) enum MiddlewareStack {
+    Fixed(Arc<Stack>),
+    Swappable(Arc<ArcSwap<Stack>>),
+}
+
+impl MiddlewareStack {
+    pub(crate) fn fixed(stack: Stack) -> Self {
+        Self::Fixed(Arc::new(stack))
+    }
+
+    pub(crate) fn swappable(stack: Stack) -> Self {
+        Self::Swappable(Arc::new(ArcSwap::from_pointee(stack)))
+    }
+
+    /// Returns the current stack. A request keeps using it until it's done, even if the stack is
+    /// replaced in the meantime.
+    pub(crate) fn load(&self) -> Arc<Stack> {
+        match self {
+            Self::Fixed(stack) => stack.clone(),
+            Self::Swappable(stack) => stack.load_full(),
+        }
+    }
+
+    pub(crate) fn handle(&self) -> Option<StackHandle> {
+        match self {
+            Self::Fixed(_) => None,
+            Self::Swappable(stack) => Some(StackHandle {
+                stack: stack.clone(),
+            }),
+        }
+    }
+}
+
+/// A handle to replace the middleware of a running client, created with
+/// [`ClientBuilder::build_swappable`](crate::ClientBuilder::build_swappable).
+///
+/// The new middleware apply to requests sent after the swap, while in-flight requests finish
+/// with the middleware they started with. The handle is shared by all the clones of the client.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use rquest_middleware::{map_request, ClientBuilder, Error};
+///
+/// let (client, handle) = ClientBuilder::new(rquest::Client::new()).build_swappable();
+///
+/// // Stop sending requests without restarting the service.
+/// handle.update(|stack| {
+///     stack.insert(
+///         0,
+///         Arc::new(map_request(|_req| async move {
+///             Err(Error::middleware(std::io::Error::other("kill switch enabled")))
+///         })),
+///     )
+/// });
+/// assert_eq!(client.middleware().len(), 1);
+///
+/// // And back.
+/// handle.replace(Vec::new());
+/// assert_eq!(client.middleware().len(), 0);
+/// ```
+#[derive(Clone)]
+pub struct StackHandle {
+    stack: Arc<ArcSwap<Stack>>,
+}
+
+impl StackHandle {
+    /// Returns an iterator over the current middleware, in the order they run.
+    pub fn middleware(&self) -> impl ExactSizeIterator<Item = Arc<dyn Middleware>> {
+        iter(self.stack.load_full())
+    }
+
+    /// Replaces the middleware of the client.
+    pub fn replace<I>(&self, middleware: I)
+    where
+        I: IntoIterator<Item = Arc<dyn Middleware>>,
+    {
+        self.stack.store(Arc::new(middleware.into_iter().collect()));
+    }
+
+    /// Edits the middleware of the client, e.g. to insert or remove one.
+    ///
+    /// `f` is given a copy of the current middleware. It may be called more than once if the
+    /// stack is updated concurrently.
+    pub fn update<F>(&self, mut f: F)
+    where
+        F: FnMut(&mut Vec<Arc<dyn Middleware>>),
+    {
+        self.stack.rcu(|current| {
+            let mut stack = Stack::clone(current);
+            f(&mut stack);
+            stack
+        });
+    }
+}
+
+impl fmt::Debug for StackHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StackHandle")
+            .field("middleware", &self.stack.load_full())
+            .finish()
+    }
+}
+
+/// Iterates over a snapshot of the stack.
+pub(crate) fn iter(stack: Arc<Stack>) -> impl ExactSizeIterator<Item = Arc<dyn Middleware>> {
+    (0..stack.len()).map(move |index| stack[index].clone())
+}
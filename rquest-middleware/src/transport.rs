@@ -0,0 +1,52 @@
+use http::Extensions;
+use rquest::{Client, Request};
+
+use crate::error::Result;
+use crate::response::Response;
+
+/// A `Transport` sends requests once they have made it through the whole middleware chain.
+///
+/// By default a [`ClientWithMiddleware`] sends requests using the [`rquest::Client`] it was built
+/// with. Use [`ClientBuilder::with_transport`] to plug in something else, e.g. an in-memory
+/// transport for tests.
+///
+/// # Example
+///
+/// ```
+/// use http::Extensions;
+/// use rquest::Request;
+/// use rquest_middleware::{ClientBuilder, Response, Result, Transport};
+///
+/// struct AlwaysOk;
+///
+/// #[async_trait::async_trait]
+/// impl Transport for AlwaysOk {
+///     async fn execute(&self, req: Request, _extensions: &mut Extensions) -> Result<Response> {
+///         Response::builder().url(req.url().clone()).body("ok")
+///     }
+/// }
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with_transport(AlwaysOk)
+///     .build();
+/// ```
+///
+/// [`ClientWithMiddleware`]: crate::ClientWithMiddleware
+/// [`ClientBuilder::with_transport`]: crate::ClientBuilder::with_transport
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait Transport: 'static + Send + Sync {
+    /// Sends the request, returning the response from the other end.
+    ///
+    /// The `extensions` are the ones that went through the middleware chain.
+    async fn execute(&self, req: Request, extensions: &mut Extensions) -> Result<Response>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Transport for Client {
+    async fn execute(&self, req: Request, _extensions: &mut Extensions) -> Result<Response> {
+        let resp = Client::execute(self, req).await?;
+        Ok(Response::from_original(resp))
+    }
+}
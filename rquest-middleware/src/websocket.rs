@@ -0,0 +1,208 @@
+use std::fmt::{self, Display};
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use http::Extensions;
+use rquest::header::{HeaderMap, HeaderName, HeaderValue};
+use rquest::{Client, Request};
+use serde::Serialize;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+use crate::error::{Error, Result};
+use crate::response::Response;
+use crate::transport::Transport;
+use crate::{ClientWithMiddleware, RequestBuilder};
+
+type Configure =
+    Box<dyn FnOnce(rquest::WebSocketRequestBuilder) -> rquest::WebSocketRequestBuilder + Send>;
+
+/// Errors returned by websocket handshakes through [`Error::Middleware`].
+#[derive(Debug, Error)]
+pub enum WebSocketError {
+    #[error("The websocket handshake request can only be sent once")]
+    AlreadySent,
+    #[error("The middleware chain did not perform the websocket handshake")]
+    NoHandshake,
+}
+
+/// A builder for a websocket handshake going through the middleware chain, created with
+/// [`ClientWithMiddleware::websocket`].
+///
+/// Request initialisers run when the builder is created, as for any other request. When sent,
+/// the handshake request goes through the middleware stack: middleware can modify the request
+/// url and headers, and sees a [`Response`] with the status and headers of the handshake
+/// response, without a body.
+///
+/// Sending the handshake can't be retried, middleware calling `next.run` more than once gets a
+/// [`WebSocketError::AlreadySent`] error.
+#[must_use = "WebSocketRequestBuilder does nothing until you 'send' it"]
+pub struct WebSocketRequestBuilder {
+    request: RequestBuilder,
+    configure: Option<Configure>,
+}
+
+impl WebSocketRequestBuilder {
+    pub(crate) fn new(request: RequestBuilder) -> Self {
+        Self {
+            request,
+            configure: None,
+        }
+    }
+
+    /// Add a `Header` to the handshake request.
+    pub fn header<K, V>(self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        Self {
+            request: self.request.header(key, value),
+            ..self
+        }
+    }
+
+    /// Add a set of Headers to the existing ones on the handshake request.
+    pub fn headers(self, headers: HeaderMap) -> Self {
+        Self {
+            request: self.request.headers(headers),
+            ..self
+        }
+    }
+
+    /// Enable HTTP basic authentication.
+    pub fn basic_auth<U, P>(self, username: U, password: Option<P>) -> Self
+    where
+        U: Display,
+        P: Display,
+    {
+        Self {
+            request: self.request.basic_auth(username, password),
+            ..self
+        }
+    }
+
+    /// Enable HTTP bearer authentication.
+    pub fn bearer_auth<T>(self, token: T) -> Self
+    where
+        T: Display,
+    {
+        Self {
+            request: self.request.bearer_auth(token),
+            ..self
+        }
+    }
+
+    /// Modify the query string of the URL, see [`RequestBuilder::query`].
+    pub fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        Self {
+            request: self.request.query(query),
+            ..self
+        }
+    }
+
+    /// Configure websocket specific options (e.g. protocols) on the underlying
+    /// [`rquest::WebSocketRequestBuilder`], once the request went through the middleware chain.
+    pub fn configure<F>(self, f: F) -> Self
+    where
+        F: FnOnce(rquest::WebSocketRequestBuilder) -> rquest::WebSocketRequestBuilder
+            + Send
+            + 'static,
+    {
+        let configure: Configure = match self.configure {
+            Some(previous) => Box::new(move |builder| f(previous(builder))),
+            None => Box::new(f),
+        };
+        Self {
+            configure: Some(configure),
+            ..self
+        }
+    }
+
+    /// Inserts the extension into this request builder
+    pub fn with_extension<T: Send + Sync + Clone + 'static>(self, extension: T) -> Self {
+        Self {
+            request: self.request.with_extension(extension),
+            ..self
+        }
+    }
+
+    /// Returns a mutable reference to the internal set of extensions for this request
+    pub fn extensions(&mut self) -> &mut Extensions {
+        self.request.extensions()
+    }
+
+    /// Sends the handshake request through the middleware chain, returning the websocket
+    /// response to upgrade.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the handshake failed or if any middleware returned an error.
+    pub async fn send(mut self) -> Result<rquest::WebSocketResponse> {
+        let mut extensions = std::mem::take(self.request.extensions());
+        let (client, req) = self.request.build_split();
+        let transport = HandshakeTransport {
+            client: client.inner.clone(),
+            configure: Mutex::new(Some(self.configure)),
+            response: Mutex::new(None),
+        };
+
+        client.run_chain(&transport, req?, &mut extensions).await?;
+
+        let response = transport
+            .response
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner());
+        response.ok_or_else(|| Error::middleware(WebSocketError::NoHandshake))
+    }
+}
+
+impl fmt::Debug for WebSocketRequestBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WebSocketRequestBuilder")
+            .field("request", &self.request)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Performs the websocket handshake at the end of the middleware chain, keeping hold of the
+/// websocket response while middleware only sees its status and headers.
+struct HandshakeTransport {
+    client: Client,
+    configure: Mutex<Option<Option<Configure>>>,
+    response: Mutex<Option<rquest::WebSocketResponse>>,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl Transport for HandshakeTransport {
+    async fn execute(&self, req: Request, _extensions: &mut Extensions) -> Result<Response> {
+        let configure = self
+            .configure
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .ok_or_else(|| Error::middleware(WebSocketError::AlreadySent))?;
+
+        let mut builder = self
+            .client
+            .websocket(req.url().clone())
+            .headers(req.headers().clone());
+        if let Some(configure) = configure {
+            builder = configure(builder);
+        }
+        let ws = builder.send().await?;
+
+        let mut response = Response::builder()
+            .status(ws.status())
+            .version(ws.version())
+            .url(ws.url().clone())
+            .body(Bytes::new())?;
+        *response.headers_mut() = ws.headers().clone();
+
+        *self.response.lock().unwrap_or_else(|e| e.into_inner()) = Some(ws);
+        Ok(response)
+    }
+}
@@ -0,0 +1,113 @@
+//! Counts the allocations of a request going through the middleware chain. This lives in its own
+//! test binary as it replaces the global allocator.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use http::Extensions;
+use rquest::{Method, Request};
+use rquest_middleware::{
+    ClientBuilder, ClientWithMiddleware, Middleware, Next, Response, Result, StaticMiddleware,
+    StaticNext, StaticStack, Transport,
+};
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts the allocations made by each thread.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // Fails while the thread local is being destroyed, such allocations aren't measured.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+struct NoopTransport;
+
+#[async_trait::async_trait]
+impl Transport for NoopTransport {
+    async fn execute(&self, _req: Request, _extensions: &mut Extensions) -> Result<Response> {
+        Response::builder().body("")
+    }
+}
+
+struct Passthrough;
+
+#[async_trait::async_trait]
+impl Middleware for Passthrough {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        next.run(req, extensions).await
+    }
+}
+
+impl StaticMiddleware for Passthrough {
+    async fn handle<N: StaticNext>(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: N,
+    ) -> Result<Response> {
+        next.run(req, extensions).await
+    }
+}
+
+fn builder() -> ClientBuilder {
+    ClientBuilder::new(rquest::Client::new()).with_transport(NoopTransport)
+}
+
+/// The number of allocations made by executing a request with `client`.
+fn allocations(client: &ClientWithMiddleware) -> usize {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let url: rquest::Url = "http://example.com".parse().unwrap();
+    let execute = || {
+        let req = Request::new(Method::GET, url.clone());
+        let before = ALLOCATIONS.with(Cell::get);
+        runtime.block_on(client.execute(req)).unwrap();
+        ALLOCATIONS.with(Cell::get) - before
+    };
+    // The first request may initialise some state lazily.
+    execute();
+    execute()
+}
+
+#[test]
+fn dyn_middleware_allocate_for_each_hop() {
+    let one = builder().with(Passthrough).build();
+    let eight = (0..8)
+        .fold(builder(), |builder, _| builder.with(Passthrough))
+        .build();
+    assert_eq!(allocations(&eight), allocations(&one) + 7);
+}
+
+#[test]
+fn static_stacks_allocate_once_whatever_their_depth() {
+    let one = builder().with(StaticStack::new().with(Passthrough)).build();
+    let eight = StaticStack::new()
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough)
+        .with(Passthrough);
+    let eight = builder().with(eight).build();
+    assert_eq!(allocations(&eight), allocations(&one));
+}
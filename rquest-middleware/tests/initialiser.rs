@@ -0,0 +1,150 @@
+use http::Extensions;
+use rquest::header::{HeaderValue, USER_AGENT};
+use rquest::{Method, Request};
+use rquest_middleware::{
+    AsyncRequestInitialiser, ClientBuilder, Error, Extension, RequestBuilder, RequestInitialiser,
+    Response, Result, Transport,
+};
+
+#[derive(Clone)]
+struct Name(&'static str);
+
+/// Answers with the user agent and the `Name` extension of the request.
+struct EchoTransport;
+
+#[async_trait::async_trait]
+impl Transport for EchoTransport {
+    async fn execute(&self, req: Request, extensions: &mut Extensions) -> Result<Response> {
+        let agent = req
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|agent| agent.to_str().ok())
+            .unwrap_or("none");
+        let name = extensions.get::<Name>().map_or("none", |name| name.0);
+        Response::builder().body(format!("{agent} {name}"))
+    }
+}
+
+struct UserAgent;
+
+impl RequestInitialiser for UserAgent {
+    fn init(&self, req: RequestBuilder) -> RequestBuilder {
+        req.header(USER_AGENT, "agent")
+    }
+
+    fn init_request(&self, req: &mut Request, _extensions: &mut Extensions) {
+        req.headers_mut()
+            .entry(USER_AGENT)
+            .or_insert(HeaderValue::from_static("agent"));
+    }
+}
+
+fn client() -> rquest_middleware::ClientWithMiddleware {
+    ClientBuilder::new(rquest::Client::new())
+        .with_init(UserAgent)
+        .with_init(Extension(Name("default")))
+        .with_transport(EchoTransport)
+        .build()
+}
+
+#[tokio::test]
+async fn initialisers_run_on_execute() {
+    let req = Request::new(Method::GET, "http://example.com".parse().unwrap());
+    let res = client().execute(req).await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "agent default");
+}
+
+#[tokio::test]
+async fn request_extensions_take_precedence_over_initialisers() {
+    let req = Request::new(Method::GET, "http://example.com".parse().unwrap());
+    let mut extensions = Extensions::new();
+    extensions.insert(Name("custom"));
+    let res = client()
+        .execute_with_extensions(req, &mut extensions)
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "agent custom");
+
+    let res = client()
+        .get("http://example.com")
+        .with_extension(Name("custom"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "agent custom");
+}
+
+/// Looks up the name asynchronously, failing when the request has no user agent.
+struct LookupName;
+
+#[async_trait::async_trait]
+impl AsyncRequestInitialiser for LookupName {
+    async fn init(&self, req: &mut Request, extensions: &mut Extensions) -> Result<()> {
+        tokio::task::yield_now().await;
+        if !req.headers().contains_key(USER_AGENT) {
+            return Err(Error::Middleware(anyhow::anyhow!("no user agent")));
+        }
+        extensions.insert(Name("looked up"));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn async_initialisers_run_after_sync_initialisers() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with_async_init(LookupName)
+        .with_init(UserAgent)
+        .with_transport(EchoTransport)
+        .build();
+
+    let res = client.get("http://example.com").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "agent looked up");
+}
+
+#[tokio::test]
+async fn async_initialiser_errors_abort_the_request() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with_async_init(LookupName)
+        .with_transport(EchoTransport)
+        .build();
+
+    let req = Request::new(Method::GET, "http://example.com".parse().unwrap());
+    let err = client.execute(req).await.unwrap_err();
+    assert_eq!(err.to_string(), "no user agent");
+}
+
+/// Records the name it saw in the extensions.
+struct RecordName;
+
+#[async_trait::async_trait]
+impl rquest_middleware::Middleware for RecordName {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: rquest_middleware::Next<'_>,
+    ) -> Result<Response> {
+        let seen = extensions.get::<Name>().map_or("none", |name| name.0);
+        extensions.insert(Seen(seen));
+        next.run(req, extensions).await
+    }
+}
+
+#[derive(Clone)]
+struct Seen(&'static str);
+
+#[tokio::test]
+async fn send_with_extensions_returns_the_final_extensions() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with_init(Extension(Name("default")))
+        .with(RecordName)
+        .with_transport(EchoTransport)
+        .build();
+
+    let (res, extensions) = client
+        .get("http://example.com")
+        .send_with_extensions()
+        .await;
+    assert!(res.is_ok());
+    assert_eq!(extensions.get::<Seen>().unwrap().0, "default");
+}
@@ -0,0 +1,397 @@
+use std::sync::Arc;
+
+use http::Extensions;
+use rquest::Request;
+use rquest_middleware::matchers::{host, path_prefix};
+use rquest_middleware::{
+    from_fn, map_err, map_request, map_response, ClientBuilder, ClientWithMiddleware, Conditional,
+    Error, Extension, Middleware, Named, Next, Response, Result, SkipMiddleware, StaticMiddleware,
+    StaticNext, StaticStack, Transport,
+};
+
+#[derive(Clone, Default)]
+struct Trail(Vec<&'static str>);
+
+/// Answers with the names of the middleware the request went through.
+struct TrailTransport;
+
+#[async_trait::async_trait]
+impl Transport for TrailTransport {
+    async fn execute(&self, _req: Request, extensions: &mut Extensions) -> Result<Response> {
+        let trail = extensions.get::<Trail>().cloned().unwrap_or_default();
+        Response::builder().body(trail.0.join(","))
+    }
+}
+
+/// Appends its name to the `Trail` extension.
+struct Mark(&'static str);
+
+#[async_trait::async_trait]
+impl Middleware for Mark {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let mut trail = extensions.remove::<Trail>().unwrap_or_default();
+        trail.0.push(self.0);
+        extensions.insert(trail);
+        next.run(req, extensions).await
+    }
+
+    fn name(&self) -> &str {
+        self.0
+    }
+}
+
+impl StaticMiddleware for Mark {
+    async fn handle<N: StaticNext>(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: N,
+    ) -> Result<Response> {
+        let mut trail = extensions.remove::<Trail>().unwrap_or_default();
+        trail.0.push(self.0);
+        extensions.insert(trail);
+        next.run(req, extensions).await
+    }
+}
+
+struct Other;
+
+#[async_trait::async_trait]
+impl Middleware for Other {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        Middleware::handle(&Mark("other"), req, extensions, next).await
+    }
+}
+
+fn client() -> ClientWithMiddleware {
+    ClientBuilder::new(rquest::Client::new())
+        .with(Mark("a"))
+        .with(Other)
+        .with(Mark("b"))
+        .with_transport(TrailTransport)
+        .build()
+}
+
+async fn trail(skip: Option<SkipMiddleware>) -> String {
+    let mut req = client().get("http://example.com");
+    if let Some(skip) = skip {
+        req = req.with_extension(skip);
+    }
+    req.send().await.unwrap().text().await.unwrap()
+}
+
+#[tokio::test]
+async fn middleware_can_be_skipped_per_request() {
+    assert_eq!(trail(None).await, "a,other,b");
+    assert_eq!(trail(Some(SkipMiddleware::named("a"))).await, "other,b");
+    assert_eq!(trail(Some(SkipMiddleware::of::<Other>())).await, "a,b");
+    assert_eq!(
+        trail(Some(SkipMiddleware::of::<Mark>().and::<Other>())).await,
+        ""
+    );
+    assert_eq!(
+        trail(Some(SkipMiddleware::named("b").and_named("c"))).await,
+        "a,other"
+    );
+}
+
+#[tokio::test]
+async fn conditional_middleware_only_runs_for_matching_requests() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with_if(host("api.example.com"), Mark("api"))
+        .with(Conditional::new(path_prefix("/admin"), Mark("admin")))
+        .with(Mark("always"))
+        .with_transport(TrailTransport)
+        .build();
+
+    let trail = |url: &'static str| {
+        let client = client.clone();
+        async move { client.get(url).send().await.unwrap().text().await.unwrap() }
+    };
+    assert_eq!(trail("http://example.com/").await, "always");
+    assert_eq!(trail("http://api.example.com/").await, "api,always");
+    assert_eq!(
+        trail("http://example.com/admin/users").await,
+        "admin,always"
+    );
+    assert_eq!(
+        trail("http://api.example.com/admin").await,
+        "api,admin,always"
+    );
+}
+
+#[tokio::test]
+async fn from_fn_forwards_requests_with_their_extensions() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(Mark("a"))
+        .with(from_fn(|req, mut next| async move {
+            let trail = next.extensions_mut().get_mut::<Trail>().unwrap();
+            trail.0.push("fn");
+            next.run(req).await
+        }))
+        .with(Mark("b"))
+        .with_transport(TrailTransport)
+        .build();
+
+    let (res, extensions) = client
+        .get("http://example.com")
+        .send_with_extensions()
+        .await;
+    assert_eq!(res.unwrap().text().await.unwrap(), "a,fn,b");
+    assert_eq!(extensions.get::<Trail>().unwrap().0, ["a", "fn", "b"]);
+}
+
+#[tokio::test]
+async fn from_fn_can_answer_without_the_chain() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(Mark("a"))
+        .with(from_fn(|_req, _next| async move {
+            Response::builder().status(204).body("")
+        }))
+        .with(Mark("b"))
+        .with_transport(TrailTransport)
+        .build();
+
+    let (res, extensions) = client
+        .get("http://example.com")
+        .send_with_extensions()
+        .await;
+    assert_eq!(res.unwrap().status(), 204);
+    assert_eq!(extensions.get::<Trail>().unwrap().0, ["a"]);
+}
+
+/// Fails requests to `/mapped`.
+struct FailOnMapped;
+
+#[async_trait::async_trait]
+impl Transport for FailOnMapped {
+    async fn execute(&self, req: Request, _extensions: &mut Extensions) -> Result<Response> {
+        let status = if req.url().path() == "/mapped" {
+            500
+        } else {
+            200
+        };
+        Response::builder().status(status).body("")
+    }
+}
+
+#[tokio::test]
+async fn map_helpers() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(map_err(|err: Error| {
+            Error::Middleware(anyhow::anyhow!("mapped: {err}"))
+        }))
+        .with(map_response(
+            |res| async move { res.error_for_status().await },
+        ))
+        .with(map_request(|mut req| async move {
+            req.url_mut().set_path("/mapped");
+            Ok(req)
+        }))
+        .with_transport(FailOnMapped)
+        .build();
+
+    let err = client.get("http://example.com").send().await.unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("mapped: HTTP status server error (500"));
+}
+
+#[tokio::test]
+async fn static_stacks_run_in_order_within_the_chain() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(Mark("a"))
+        .with(StaticStack::new().with(Mark("b")).with(Mark("c")))
+        .with(Mark("d"))
+        .with_transport(TrailTransport)
+        .build();
+
+    let res = client.get("http://example.com").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "a,b,c,d");
+}
+
+#[tokio::test]
+async fn hooks_run_around_the_chain() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(Mark("middleware"))
+        .on_request(
+            |_: &mut Request, extensions: &mut Extensions| -> Result<()> {
+                extensions.insert(Trail(vec!["request"]));
+                Ok(())
+            },
+        )
+        .on_response(|res: &mut Result<Response>, extensions: &mut Extensions| {
+            extensions.get_mut::<Trail>().unwrap().0.push("response");
+            if let Ok(res) = res {
+                res.headers_mut()
+                    .insert("x-hooked", "true".parse().unwrap());
+            }
+        })
+        .with_transport(TrailTransport)
+        .build();
+
+    let (res, extensions) = client
+        .get("http://example.com")
+        .send_with_extensions()
+        .await;
+    let res = res.unwrap();
+    assert_eq!(res.headers()["x-hooked"], "true");
+    assert_eq!(res.text().await.unwrap(), "request,middleware");
+    assert_eq!(
+        extensions.get::<Trail>().unwrap().0,
+        ["request", "middleware", "response"]
+    );
+}
+
+#[tokio::test]
+async fn request_hook_errors_skip_the_chain() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(Mark("middleware"))
+        .on_request(|_: &mut Request, _: &mut Extensions| -> Result<()> {
+            Err(Error::Middleware(anyhow::anyhow!("rejected")))
+        })
+        .on_response(|res: &mut Result<Response>, _: &mut Extensions| {
+            if res.is_err() {
+                *res = Response::builder().status(503).body("");
+            }
+        })
+        .with_transport(TrailTransport)
+        .build();
+
+    let (res, extensions) = client
+        .get("http://example.com")
+        .send_with_extensions()
+        .await;
+    assert_eq!(res.unwrap().status(), 503);
+    assert!(extensions.get::<Trail>().is_none());
+}
+
+#[test]
+fn stack_is_listed_and_debug_rendered() {
+    let builder = ClientBuilder::new(rquest::Client::new())
+        .with_init(Named::new("trail", Extension(Trail::default())))
+        .with(Mark("a"))
+        .with(Named::new("renamed", Other));
+    assert_eq!(
+        builder.middleware().map(|m| m.name()).collect::<Vec<_>>(),
+        ["a", "renamed"]
+    );
+
+    let client = builder.build();
+    assert_eq!(
+        client.initialisers().map(|i| i.name()).collect::<Vec<_>>(),
+        ["trail"]
+    );
+    let debug = format!("{client:?}");
+    assert!(debug.contains("initialisers: [trail]"), "{debug}");
+    assert!(debug.contains("middleware: [a, renamed]"), "{debug}");
+}
+
+#[tokio::test]
+async fn named_middleware_can_still_be_skipped_by_type() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(Named::new("renamed", Other))
+        .with(Mark("a"))
+        .with_transport(TrailTransport)
+        .build();
+
+    for skip in [
+        SkipMiddleware::of::<Other>(),
+        SkipMiddleware::named("renamed"),
+    ] {
+        let res = client
+            .get("http://example.com")
+            .with_extension(skip)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "a");
+    }
+}
+
+#[tokio::test]
+async fn derived_clients_edit_the_stack() {
+    let base = client();
+    let names = |client: &ClientWithMiddleware| {
+        client
+            .middleware()
+            .map(|m| m.name().to_owned())
+            .collect::<Vec<_>>()
+    };
+
+    let derived = base
+        .derive()
+        .insert(0, Mark("first"))
+        .without::<Other>()
+        .replace_named("b", Mark("c"))
+        .build();
+    assert_eq!(names(&derived), ["first", "a", "c"]);
+    let res = derived.get("http://example.com").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "first,a,c");
+
+    let derived = base
+        .derive()
+        .replace::<Other, _>(Mark("replaced"))
+        .without_named("a")
+        .build();
+    assert_eq!(names(&derived), ["replaced", "b"]);
+
+    // The base client is left untouched.
+    let res = base.get("http://example.com").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "a,other,b");
+}
+
+/// Waits before forwarding the request.
+struct Slow(std::time::Duration);
+
+#[async_trait::async_trait]
+impl Middleware for Slow {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        tokio::time::sleep(self.0).await;
+        next.run(req, extensions).await
+    }
+}
+
+#[tokio::test]
+async fn swapped_stacks_apply_to_new_requests_only() {
+    let (client, handle) = ClientBuilder::new(rquest::Client::new())
+        .with(Slow(std::time::Duration::from_millis(200)))
+        .with(Mark("old"))
+        .with_transport(TrailTransport)
+        .build_swappable();
+    assert!(client.stack_handle().is_some());
+    assert!(self::client().stack_handle().is_none());
+
+    let in_flight = tokio::spawn({
+        let client = client.clone();
+        async move { client.get("http://example.com").send().await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    handle.replace(vec![Arc::new(Mark("new")) as Arc<dyn Middleware>]);
+    let res = client.get("http://example.com").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "new");
+
+    let res = in_flight.await.unwrap().unwrap();
+    assert_eq!(res.text().await.unwrap(), "old");
+
+    handle.update(|stack| stack.push(Arc::new(Mark("more"))));
+    let res = client.get("http://example.com").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "new,more");
+    assert_eq!(handle.middleware().len(), 2);
+}
@@ -0,0 +1,105 @@
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use rquest_middleware::{ClientWithMiddleware, Response};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Sends a request to a server answering with `body`, returning the still streaming response.
+async fn fetch(body: &[u8]) -> (MockServer, Response) {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+        .mount(&server)
+        .await;
+
+    let client = ClientWithMiddleware::from(rquest::Client::new());
+    let res = client.get(server.uri()).send().await.unwrap();
+    (server, res)
+}
+
+#[tokio::test]
+async fn streaming_body_reads_in_chunks() {
+    let body = vec![b'a'; 1024 * 1024];
+    let (_server, mut res) = fetch(&body).await;
+    assert!(!res.is_buffered());
+    assert!(res.buffered_bytes().is_none());
+
+    let mut read = Vec::new();
+    while let Some(chunk) = res.chunk().await.unwrap() {
+        assert!(!chunk.is_empty());
+        read.extend_from_slice(&chunk);
+    }
+    assert_eq!(read, body);
+    assert!(res.chunk().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn try_clone_needs_a_buffered_body() {
+    let (_server, res) = fetch(b"hello").await;
+    assert!(res.try_clone().is_none());
+
+    let res = res.buffer().await.unwrap();
+    assert!(res.is_buffered());
+    assert_eq!(res.buffered_bytes().unwrap().as_ref(), b"hello");
+    let clone = res.try_clone().unwrap();
+    assert_eq!(clone.status(), res.status());
+    assert_eq!(clone.text().await.unwrap(), "hello");
+    assert_eq!(res.text().await.unwrap(), "hello");
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn json_after_buffer() {
+    use std::collections::HashMap;
+
+    let (_server, res) = fetch(br#"{"answer":42}"#).await;
+    let res = res.buffer().await.unwrap();
+    let json: HashMap<String, u32> = res.try_clone().unwrap().json().await.unwrap();
+    assert_eq!(json["answer"], 42);
+    assert_eq!(res.bytes().await.unwrap().as_ref(), br#"{"answer":42}"#);
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn bytes_stream_yields_the_whole_body() {
+    use futures_util::StreamExt;
+
+    let body = vec![b'a'; 1024 * 1024];
+    for buffered in [false, true] {
+        let (_server, mut res) = fetch(&body).await;
+        if buffered {
+            res = res.buffer().await.unwrap();
+        }
+        let mut stream = Box::pin(res.bytes_stream());
+        let mut read = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            read.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(read, body, "buffered: {buffered}");
+    }
+}
+
+#[tokio::test]
+async fn buffer_up_to_keeps_long_bodies_streaming() {
+    let body = vec![b'a'; 1024 * 1024];
+    let (_server, res) = fetch(&body).await;
+    let res = res.buffer_up_to(body.len()).await.unwrap();
+    assert_eq!(res.buffered_bytes().unwrap().len(), body.len());
+
+    let (_server, res) = fetch(&body).await;
+    let res = res.buffer_up_to(body.len() - 1).await.unwrap();
+    assert!(!res.is_buffered());
+    assert_eq!(res.bytes().await.unwrap(), body);
+}
+
+#[tokio::test]
+async fn converting_to_http_needs_a_buffered_body() {
+    let (_server, res) = fetch(b"hello").await;
+    let res = http::Response::<Bytes>::try_from(res).unwrap_err();
+    assert!(!res.is_buffered());
+
+    let res = http::Response::<Bytes>::try_from(res.buffer().await.unwrap()).unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body().as_ref(), b"hello");
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "tower")]
+
+use std::time::Duration;
+
+use http::Extensions;
+use rquest::Request;
+use rquest_middleware::{
+    ClientBuilder, Error, Extension, Response, Result, TowerMiddleware, Transport,
+};
+use tower::limit::ConcurrencyLimitLayer;
+use tower::timeout::TimeoutLayer;
+
+#[derive(Clone)]
+struct Delay(Duration);
+
+/// Answers with the `Delay` found in the extensions after sleeping for it.
+struct SleepyTransport;
+
+#[async_trait::async_trait]
+impl Transport for SleepyTransport {
+    async fn execute(&self, _req: Request, extensions: &mut Extensions) -> Result<Response> {
+        let Delay(delay) = extensions.get::<Delay>().cloned().expect("missing delay");
+        tokio::time::sleep(delay).await;
+        Response::builder().body("done")
+    }
+}
+
+#[tokio::test]
+async fn layer_errors_are_mapped_to_middleware_errors() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with_init(Extension(Delay(Duration::from_secs(10))))
+        .with(TowerMiddleware::new(TimeoutLayer::new(
+            Duration::from_millis(10),
+        )))
+        .with_transport(SleepyTransport)
+        .build();
+
+    let err = client.get("http://example.com").send().await.unwrap_err();
+    match err {
+        Error::Middleware(err) => assert!(err.to_string().contains("timed out")),
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+#[tokio::test]
+async fn requests_are_forwarded_with_their_extensions() {
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(TowerMiddleware::new(ConcurrencyLimitLayer::new(1)))
+        .with(TowerMiddleware::new(TimeoutLayer::new(
+            Duration::from_secs(10),
+        )))
+        .with_transport(SleepyTransport)
+        .build();
+
+    let (a, b) = tokio::join!(
+        client
+            .get("http://example.com")
+            .with_extension(Delay(Duration::from_millis(1)))
+            .send(),
+        client
+            .get("http://example.com")
+            .with_extension(Delay(Duration::from_millis(1)))
+            .send(),
+    );
+    assert_eq!(a.unwrap().text().await.unwrap(), "done");
+    assert_eq!(b.unwrap().text().await.unwrap(), "done");
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "websocket")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use http::Extensions;
+use rquest::Request;
+use rquest_middleware::{
+    from_fn, ClientBuilder, Error, FnNext, Middleware, Next, RequestBuilder, RequestInitialiser,
+    Response, Result, WebSocketError,
+};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct Initialiser;
+
+impl RequestInitialiser for Initialiser {
+    fn init(&self, req: RequestBuilder) -> RequestBuilder {
+        req.header("x-initialiser", "yes")
+    }
+}
+
+/// Sends the request down the chain twice, returning the second result.
+struct SendTwice;
+
+#[async_trait::async_trait]
+impl Middleware for SendTwice {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let retry = req.try_clone().unwrap();
+        let _ = next.clone().run(req, extensions).await;
+        next.run(retry, extensions).await
+    }
+}
+
+/// A server answering the handshake with a `200`, so that it is recorded but fails.
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn handshake_goes_through_initialisers_and_middleware() {
+    let server = server().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with_init(Initialiser)
+        .with(from_fn(move |mut req: Request, next: FnNext| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            req.headers_mut()
+                .insert("x-middleware", "yes".parse().unwrap());
+            next.run(req)
+        }))
+        .build();
+
+    let res = client
+        .websocket(format!("ws://{}/chat", server.address()))
+        .header("x-builder", "yes")
+        .send()
+        .await;
+    assert!(res.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    let headers = &requests[0].headers;
+    assert_eq!(requests[0].url.path(), "/chat");
+    for name in ["x-initialiser", "x-middleware", "x-builder"] {
+        assert_eq!(headers[name], "yes", "{name}");
+    }
+    assert_eq!(headers["upgrade"], "websocket");
+}
+
+#[tokio::test]
+async fn handshake_is_only_sent_once() {
+    let server = server().await;
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(SendTwice)
+        .build();
+
+    let err = client
+        .websocket(format!("ws://{}/", server.address()))
+        .send()
+        .await
+        .unwrap_err();
+    match err {
+        Error::Middleware(err) => assert!(matches!(
+            err.downcast_ref(),
+            Some(WebSocketError::AlreadySent)
+        )),
+        err => panic!("unexpected error {err:?}"),
+    }
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
@@ -0,0 +1,40 @@
+//! In-process mock [`Transport`] to test [`rquest_middleware`] stacks without a server.
+//!
+//! Register [`Mock`]s on a [`MockTransport`], plug it in at the bottom of the middleware chain
+//! with [`ClientBuilder::with_transport`], then assert on what was received once the test ran.
+//!
+//! ## Example
+//!
+//! ```
+//! use rquest_middleware::{ClientBuilder, Response};
+//! use rquest_mock::{matchers::{method, path}, Mock, MockTransport};
+//!
+//! # async fn run() {
+//! let transport = MockTransport::new();
+//! transport.mount(
+//!     Mock::given(method("GET"))
+//!         .and(path("/foo"))
+//!         .respond_with(Response::builder().status(200).body("bar").unwrap())
+//!         .expect(1),
+//! );
+//!
+//! let client = ClientBuilder::new(rquest::Client::new())
+//!     .with_transport(transport.clone())
+//!     .build();
+//!
+//! let res = client.get("http://example.com/foo").send().await.unwrap();
+//! assert_eq!(res.text().await.unwrap(), "bar");
+//!
+//! transport.verify();
+//! # }
+//! ```
+//!
+//! [`Transport`]: rquest_middleware::Transport
+//! [`ClientBuilder::with_transport`]: rquest_middleware::ClientBuilder::with_transport
+
+pub mod matchers;
+mod mock;
+mod transport;
+
+pub use mock::{Match, Mock, MockBuilder, RecordedRequest, Respond};
+pub use transport::{MockError, MockTransport};
@@ -0,0 +1,128 @@
+//! Built-in [`Match`] implementations to use with [`Mock::given`](crate::Mock::given).
+use bytes::Bytes;
+use rquest::header::{HeaderName, HeaderValue};
+use rquest::Method;
+
+use crate::{Match, RecordedRequest};
+
+/// Matches the request method.
+pub struct MethodMatcher(Method);
+
+/// Matches requests with the given method, e.g. `method("GET")`.
+///
+/// # Panics
+///
+/// Panics if `method` is not a valid HTTP method.
+pub fn method<T: AsRef<str>>(method: T) -> MethodMatcher {
+    let method = Method::from_bytes(method.as_ref().as_bytes()).expect("invalid HTTP method");
+    MethodMatcher(method)
+}
+
+impl Match for MethodMatcher {
+    fn matches(&self, req: &RecordedRequest) -> bool {
+        req.method == self.0
+    }
+}
+
+/// Matches the request path.
+pub struct PathMatcher(String);
+
+/// Matches requests with exactly the given path, e.g. `path("/foo")`.
+pub fn path<T: Into<String>>(path: T) -> PathMatcher {
+    PathMatcher(path.into())
+}
+
+impl Match for PathMatcher {
+    fn matches(&self, req: &RecordedRequest) -> bool {
+        req.url.path() == self.0
+    }
+}
+
+/// Matches a request header.
+pub struct HeaderMatcher(HeaderName, Option<HeaderValue>);
+
+/// Matches requests with a header set to the given value.
+///
+/// # Panics
+///
+/// Panics if `key` or `value` are not valid header names or values.
+pub fn header<K: AsRef<str>, V: AsRef<str>>(key: K, value: V) -> HeaderMatcher {
+    let key = HeaderName::from_bytes(key.as_ref().as_bytes()).expect("invalid header name");
+    let value = HeaderValue::from_str(value.as_ref()).expect("invalid header value");
+    HeaderMatcher(key, Some(value))
+}
+
+/// Matches requests with the given header, whatever its value.
+///
+/// # Panics
+///
+/// Panics if `key` is not a valid header name.
+pub fn header_exists<K: AsRef<str>>(key: K) -> HeaderMatcher {
+    let key = HeaderName::from_bytes(key.as_ref().as_bytes()).expect("invalid header name");
+    HeaderMatcher(key, None)
+}
+
+impl Match for HeaderMatcher {
+    fn matches(&self, req: &RecordedRequest) -> bool {
+        match &self.1 {
+            Some(value) => req.headers.get_all(&self.0).iter().any(|v| v == value),
+            None => req.headers.contains_key(&self.0),
+        }
+    }
+}
+
+/// Matches the request body.
+pub struct BodyMatcher(Bytes);
+
+/// Matches requests with exactly the given body.
+pub fn body<T: Into<Bytes>>(body: T) -> BodyMatcher {
+    BodyMatcher(body.into())
+}
+
+impl Match for BodyMatcher {
+    fn matches(&self, req: &RecordedRequest) -> bool {
+        req.body == self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> RecordedRequest {
+        let mut headers = rquest::header::HeaderMap::new();
+        headers.insert("x-foo", HeaderValue::from_static("bar"));
+        RecordedRequest {
+            method: Method::POST,
+            url: "http://example.com/foo?a=b".parse().unwrap(),
+            headers,
+            body: Bytes::from_static(b"hello"),
+        }
+    }
+
+    #[test]
+    fn method_matches_case_sensitive_method() {
+        assert!(method("POST").matches(&request()));
+        assert!(!method("GET").matches(&request()));
+    }
+
+    #[test]
+    fn path_ignores_query() {
+        assert!(path("/foo").matches(&request()));
+        assert!(!path("/foo?a=b").matches(&request()));
+    }
+
+    #[test]
+    fn header_matches_value() {
+        assert!(header("x-foo", "bar").matches(&request()));
+        assert!(!header("x-foo", "baz").matches(&request()));
+        assert!(header_exists("x-foo").matches(&request()));
+        assert!(!header_exists("x-bar").matches(&request()));
+    }
+
+    #[test]
+    fn body_matches_exact_bytes() {
+        assert!(body("hello").matches(&request()));
+        assert!(!body("hell").matches(&request()));
+    }
+}
@@ -0,0 +1,132 @@
+use bytes::Bytes;
+use rquest::header::HeaderMap;
+use rquest::{Method, Request, Url};
+use rquest_middleware::{Error, Response, Result};
+
+use crate::MockError;
+
+/// A copy of a request received by a [`MockTransport`](crate::MockTransport).
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    /// The request body, empty if there was none or if it was a stream.
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    pub(crate) fn new(req: &Request) -> Self {
+        let body = req
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(Bytes::copy_from_slice)
+            .unwrap_or_default();
+        Self {
+            method: req.method().clone(),
+            url: req.url().clone(),
+            headers: req.headers().clone(),
+            body,
+        }
+    }
+}
+
+/// Decides whether a [`Mock`] applies to a request, see the [`matchers`](crate::matchers) module.
+pub trait Match: 'static + Send + Sync {
+    fn matches(&self, req: &RecordedRequest) -> bool;
+}
+
+impl<F> Match for F
+where
+    F: Send + Sync + 'static + Fn(&RecordedRequest) -> bool,
+{
+    fn matches(&self, req: &RecordedRequest) -> bool {
+        (self)(req)
+    }
+}
+
+/// Produces the response of a [`Mock`].
+///
+/// It's implemented for buffered [`Response`]s, which are cloned for every call, and for closures.
+pub trait Respond: 'static + Send + Sync {
+    fn respond(&self, req: &RecordedRequest) -> Result<Response>;
+}
+
+impl Respond for Response {
+    fn respond(&self, _req: &RecordedRequest) -> Result<Response> {
+        self.try_clone()
+            .ok_or_else(|| Error::middleware(MockError::UnbufferedResponse))
+    }
+}
+
+impl<F> Respond for F
+where
+    F: Send + Sync + 'static + Fn(&RecordedRequest) -> Result<Response>,
+{
+    fn respond(&self, req: &RecordedRequest) -> Result<Response> {
+        (self)(req)
+    }
+}
+
+/// A canned response for requests matching all of its matchers.
+///
+/// Build one with [`Mock::given`] and register it with
+/// [`MockTransport::mount`](crate::MockTransport::mount).
+pub struct Mock {
+    pub(crate) name: Option<String>,
+    pub(crate) matchers: Vec<Box<dyn Match>>,
+    pub(crate) responder: Box<dyn Respond>,
+    pub(crate) expected_calls: Option<u64>,
+}
+
+impl Mock {
+    /// Start building a mock applying to requests matched by `matcher`.
+    pub fn given<M: Match>(matcher: M) -> MockBuilder {
+        MockBuilder {
+            name: None,
+            matchers: vec![Box::new(matcher)],
+        }
+    }
+
+    /// Set how many times this mock is expected to be called, checked by
+    /// [`MockTransport::verify`](crate::MockTransport::verify).
+    pub fn expect(self, calls: u64) -> Self {
+        Self {
+            expected_calls: Some(calls),
+            ..self
+        }
+    }
+}
+
+/// A [`Mock`] that is still missing its response.
+pub struct MockBuilder {
+    name: Option<String>,
+    matchers: Vec<Box<dyn Match>>,
+}
+
+impl MockBuilder {
+    /// Add another matcher, all of them must match for the mock to apply.
+    pub fn and<M: Match>(mut self, matcher: M) -> Self {
+        self.matchers.push(Box::new(matcher));
+        self
+    }
+
+    /// Name the mock, used in assertion messages and by
+    /// [`MockTransport::calls`](crate::MockTransport::calls).
+    pub fn named<T: Into<String>>(self, name: T) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Set the response of the mock.
+    pub fn respond_with<R: Respond>(self, responder: R) -> Mock {
+        Mock {
+            name: self.name,
+            matchers: self.matchers,
+            responder: Box::new(responder),
+            expected_calls: None,
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use http::Extensions;
+use rquest::{Method, Request, Url};
+use rquest_middleware::{Error, Response, Result, Transport};
+use thiserror::Error;
+
+use crate::{Mock, RecordedRequest};
+
+/// Errors returned by a [`MockTransport`] through [`Error::Middleware`].
+#[derive(Debug, Error)]
+pub enum MockError {
+    #[error("No mock matched {method} {url}")]
+    NoMatch { method: Method, url: Url },
+    #[error("Mocked responses must have a buffered body")]
+    UnbufferedResponse,
+}
+
+struct MountedMock {
+    mock: Mock,
+    calls: u64,
+}
+
+#[derive(Default)]
+struct State {
+    mocks: Vec<MountedMock>,
+    /// Every request received, along with the index of the mock that answered it.
+    received: Vec<(RecordedRequest, Option<usize>)>,
+}
+
+/// A [`Transport`] answering requests with the first mounted [`Mock`] that matches them.
+///
+/// Requests that don't match any mock fail with [`MockError::NoMatch`]. `MockTransport` is a
+/// cheap handle: keep a clone around to make assertions after giving one to
+/// [`ClientBuilder::with_transport`].
+///
+/// [`ClientBuilder::with_transport`]: rquest_middleware::ClientBuilder::with_transport
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a mock. Mocks are tried in the order they were mounted.
+    pub fn mount(&self, mock: Mock) {
+        self.state().mocks.push(MountedMock { mock, calls: 0 });
+    }
+
+    /// Returns every request received so far, in order.
+    pub fn received_requests(&self) -> Vec<RecordedRequest> {
+        self.state()
+            .received
+            .iter()
+            .map(|(req, _)| req.clone())
+            .collect()
+    }
+
+    /// Returns how many times the mock with the given name was called.
+    pub fn calls(&self, name: &str) -> u64 {
+        self.state()
+            .mocks
+            .iter()
+            .filter(|mounted| mounted.mock.name.as_deref() == Some(name))
+            .map(|mounted| mounted.calls)
+            .sum()
+    }
+
+    /// Returns the names of the mocks that answered the received requests, in order. Unnamed
+    /// mocks and unmatched requests show up as `None`.
+    pub fn call_order(&self) -> Vec<Option<String>> {
+        let state = self.state();
+        state
+            .received
+            .iter()
+            .map(|(_, index)| index.and_then(|index| state.mocks[index].mock.name.clone()))
+            .collect()
+    }
+
+    /// Checks that every mock with an expectation set with [`Mock::expect`] was called exactly
+    /// that many times.
+    ///
+    /// # Panics
+    ///
+    /// Panics listing every mock whose expectation wasn't met.
+    pub fn verify(&self) {
+        let failures: Vec<String> = self
+            .state()
+            .mocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, mounted)| match mounted.mock.expected_calls {
+                Some(expected) if expected != mounted.calls => Some(format!(
+                    "mock {} expected {} calls but received {}",
+                    mounted
+                        .mock
+                        .name
+                        .as_deref()
+                        .unwrap_or(&format!("#{}", index)),
+                    expected,
+                    mounted.calls
+                )),
+                _ => None,
+            })
+            .collect();
+        if !failures.is_empty() {
+            panic!("Mock verification failed:\n{}", failures.join("\n"));
+        }
+    }
+
+    /// Forget all received requests and reset the call counts of the mounted mocks.
+    pub fn reset(&self) {
+        let mut state = self.state();
+        state.received.clear();
+        for mounted in &mut state.mocks {
+            mounted.calls = 0;
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panic while holding the lock can only come from a failing assertion, the state is
+        // still consistent so there's no reason to propagate the poisoning.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for MockTransport {
+    async fn execute(&self, req: Request, _extensions: &mut Extensions) -> Result<Response> {
+        let recorded = RecordedRequest::new(&req);
+        let mut state = self.state();
+
+        let index = state.mocks.iter().position(|mounted| {
+            mounted
+                .mock
+                .matchers
+                .iter()
+                .all(|matcher| matcher.matches(&recorded))
+        });
+        state.received.push((recorded.clone(), index));
+
+        let mounted = match index {
+            Some(index) => &mut state.mocks[index],
+            None => {
+                return Err(Error::middleware(MockError::NoMatch {
+                    method: recorded.method,
+                    url: recorded.url,
+                }))
+            }
+        };
+        mounted.calls += 1;
+
+        let mut response = mounted.mock.responder.respond(&recorded)?;
+        *response.url_mut() = recorded.url;
+        Ok(response)
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rquest_middleware::{ClientBuilder, ClientWithMiddleware, Response};
+use rquest_mock::matchers::{body, header, method, path};
+use rquest_mock::{Mock, MockError, MockTransport};
+use rquest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+
+fn client(transport: &MockTransport) -> ClientWithMiddleware {
+    ClientBuilder::new(rquest::Client::new())
+        .with_transport(transport.clone())
+        .build()
+}
+
+#[tokio::test]
+async fn responds_with_first_matching_mock() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("POST"))
+            .and(path("/foo"))
+            .and(header("x-api-key", "secret"))
+            .and(body("hello"))
+            .named("post")
+            .respond_with(Response::builder().status(201).body("created").unwrap())
+            .expect(1),
+    );
+    transport.mount(
+        Mock::given(method("POST"))
+            .named("fallback")
+            .respond_with(Response::builder().status(400).body("").unwrap())
+            .expect(1),
+    );
+
+    let client = client(&transport);
+    let res = client
+        .post("http://example.com/foo")
+        .header("x-api-key", "secret")
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.url().as_str(), "http://example.com/foo");
+    assert_eq!(res.text().await.unwrap(), "created");
+
+    let res = client
+        .post("http://example.com/foo")
+        .body("other")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    transport.verify();
+    assert_eq!(
+        transport.call_order(),
+        vec![Some("post".to_owned()), Some("fallback".to_owned())]
+    );
+    assert_eq!(transport.received_requests()[1].body, "other");
+}
+
+#[tokio::test]
+async fn unmatched_requests_fail() {
+    let transport = MockTransport::new();
+    let err = client(&transport)
+        .get("http://example.com/nothing")
+        .send()
+        .await
+        .unwrap_err();
+
+    match err {
+        rquest_middleware::Error::Middleware(err) => {
+            assert!(matches!(
+                err.downcast_ref(),
+                Some(MockError::NoMatch { .. })
+            ))
+        }
+        err => panic!("unexpected error {err:?}"),
+    }
+    assert_eq!(transport.call_order(), vec![None]);
+}
+
+#[tokio::test]
+async fn runs_the_middleware_stack() {
+    let attempts = Arc::new(AtomicU32::new(0));
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(path("/flaky"))
+            .named("flaky")
+            .respond_with({
+                let attempts = attempts.clone();
+                move |_: &rquest_mock::RecordedRequest| {
+                    let status = match attempts.fetch_add(1, Ordering::SeqCst) {
+                        0 => 503,
+                        _ => 200,
+                    };
+                    Response::builder().status(status).body("")
+                }
+            })
+            .expect(2),
+    );
+
+    let client = ClientBuilder::new(rquest::Client::new())
+        .with(RetryTransientMiddleware::new_with_policy(
+            ExponentialBackoff::builder()
+                .retry_bounds(Duration::from_millis(1), Duration::from_millis(10))
+                .build_with_max_retries(3),
+        ))
+        .with_transport(transport.clone())
+        .build();
+
+    let res = client.get("http://example.com/flaky").send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(transport.calls("flaky"), 2);
+    transport.verify();
+}
+
+#[tokio::test]
+#[should_panic(expected = "mock once expected 1 calls but received 0")]
+async fn verify_panics_on_unmet_expectations() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET"))
+            .named("once")
+            .respond_with(Response::builder().body("").unwrap())
+            .expect(1),
+    );
+    transport.verify();
+}
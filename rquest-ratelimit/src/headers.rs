@@ -0,0 +1,160 @@
+//! Reads the limits a server advertises in its responses.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::RETRY_AFTER;
+use http::{HeaderMap, HeaderValue, StatusCode};
+
+/// Values of `X-RateLimit-Reset` above this are Unix timestamps rather than delays.
+const TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+/// How long the server asks to hold off before the next request, if it does.
+///
+/// That's the `Retry-After` of a `429 Too Many Requests` or `503 Service Unavailable`, or the
+/// reset delay once the remaining quota reported by `RateLimit`, `RateLimit-*` or `X-RateLimit-*`
+/// headers reached zero.
+pub(crate) fn server_backoff(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+        if let Some(retry_after) = headers.get(RETRY_AFTER).and_then(parse_retry_after) {
+            return Some(retry_after);
+        }
+    }
+
+    let (remaining, reset) = structured_fields(headers)
+        .or_else(|| header_pair(headers, "ratelimit-remaining", "ratelimit-reset"))
+        .or_else(|| header_pair(headers, "x-ratelimit-remaining", "x-ratelimit-reset"))?;
+    if remaining == 0 {
+        Some(reset)
+    } else {
+        None
+    }
+}
+
+/// `Retry-After` is either delay-seconds or an HTTP date (RFC 9110 section 10.2.3).
+fn parse_retry_after(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// The `RateLimit` header of the IETF drafts, either `limit=10, remaining=0, reset=5` or
+/// `"policy";r=0;t=5`.
+fn structured_fields(headers: &HeaderMap) -> Option<(u64, Duration)> {
+    let value = headers.get("ratelimit")?.to_str().ok()?;
+    let mut remaining = None;
+    let mut reset = None;
+    for param in value.split(|c| c == ',' || c == ';') {
+        if let Some((name, value)) = param.split_once('=') {
+            match name.trim() {
+                "remaining" | "r" => remaining = value.trim().parse().ok(),
+                "reset" | "t" => reset = parse_reset(value.trim()),
+                _ => {}
+            }
+        }
+    }
+    Some((remaining?, reset?))
+}
+
+fn header_pair(headers: &HeaderMap, remaining: &str, reset: &str) -> Option<(u64, Duration)> {
+    let remaining = headers.get(remaining)?.to_str().ok()?.trim().parse().ok()?;
+    let reset = parse_reset(headers.get(reset)?.to_str().ok()?.trim())?;
+    Some((remaining, reset))
+}
+
+/// A reset is a number of seconds, possibly fractional, or a Unix timestamp as sent by e.g.
+/// GitHub.
+fn parse_reset(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        if seconds < TIMESTAMP_THRESHOLD {
+            return Some(Duration::from_secs(seconds));
+        }
+        let reset = UNIX_EPOCH.checked_add(Duration::from_secs(seconds))?;
+        return Some(reset.duration_since(SystemTime::now()).unwrap_or_default());
+    }
+    let seconds: f64 = value.parse().ok()?;
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backoff(status: u16, headers: &[(&'static str, &str)]) -> Option<Duration> {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(*name, value.parse().unwrap());
+        }
+        server_backoff(StatusCode::from_u16(status).unwrap(), &map)
+    }
+
+    #[test]
+    fn retry_after_on_too_many_requests() {
+        assert_eq!(
+            backoff(429, &[("retry-after", "7")]),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(backoff(200, &[("retry-after", "7")]), None);
+
+        let past = httpdate::fmt_http_date(UNIX_EPOCH);
+        assert_eq!(
+            backoff(503, &[("retry-after", &past)]),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn exhausted_quota_headers() {
+        let five = Some(Duration::from_secs(5));
+        assert_eq!(
+            backoff(200, &[("ratelimit", "limit=10, remaining=0, reset=5")]),
+            five
+        );
+        assert_eq!(backoff(200, &[("ratelimit", "\"default\";r=0;t=5")]), five);
+        assert_eq!(
+            backoff(
+                200,
+                &[("ratelimit-remaining", "0"), ("ratelimit-reset", "5")]
+            ),
+            five
+        );
+        assert_eq!(
+            backoff(
+                200,
+                &[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "2.5")]
+            ),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(
+            backoff(
+                200,
+                &[("x-ratelimit-remaining", "3"), ("x-ratelimit-reset", "5")]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_resets_out_of_range() {
+        for reset in [u64::MAX.to_string().as_str(), "1e20", "-1", "NaN", "inf"] {
+            assert_eq!(
+                backoff(
+                    200,
+                    &[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", reset)]
+                ),
+                None,
+                "{reset}"
+            );
+        }
+    }
+
+    #[test]
+    fn reset_timestamps() {
+        let in_a_minute = SystemTime::now() + Duration::from_secs(60);
+        let timestamp = in_a_minute.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let reset = parse_reset(&timestamp.to_string()).unwrap();
+        assert!(reset > Duration::from_secs(55) && reset <= Duration::from_secs(60));
+        assert_eq!(parse_reset("1000000000"), Some(Duration::ZERO));
+    }
+}
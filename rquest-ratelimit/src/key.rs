@@ -0,0 +1,75 @@
+use std::hash::Hash;
+
+use http::Extensions;
+use rquest::Request;
+
+/// Decides which bucket a request draws from, see [`RateLimitMiddleware::keyed`].
+///
+/// Requests for which [`extract`](Self::extract) returns `None` are not limited.
+///
+/// It's implemented for closures returning an `Option` of any hashable key:
+///
+/// ```
+/// use std::num::NonZeroU32;
+/// use rquest_ratelimit::{Quota, RateLimitMiddleware};
+///
+/// #[derive(Clone)]
+/// struct Tenant(String);
+///
+/// // One bucket per tenant, requests without a tenant are not limited.
+/// let rate_limit = RateLimitMiddleware::keyed(
+///     Quota::per_second(NonZeroU32::new(5).unwrap()),
+///     |_req: &rquest::Request, extensions: &http::Extensions| {
+///         extensions.get::<Tenant>().map(|tenant| tenant.0.clone())
+///     },
+/// );
+/// ```
+///
+/// [`RateLimitMiddleware::keyed`]: crate::RateLimitMiddleware::keyed
+pub trait KeyExtractor: 'static + Send + Sync {
+    type Key: Hash + Eq + Clone + Send + Sync + 'static;
+
+    fn extract(&self, req: &Request, extensions: &Extensions) -> Option<Self::Key>;
+}
+
+impl<F, K> KeyExtractor for F
+where
+    F: Send + Sync + 'static + Fn(&Request, &Extensions) -> Option<K>,
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type Key = K;
+
+    fn extract(&self, req: &Request, extensions: &Extensions) -> Option<K> {
+        (self)(req, extensions)
+    }
+}
+
+/// One bucket per host and port, the default of [`RateLimitMiddleware::new`].
+///
+/// [`RateLimitMiddleware::new`]: crate::RateLimitMiddleware::new
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByHost;
+
+impl KeyExtractor for ByHost {
+    type Key = (String, Option<u16>);
+
+    fn extract(&self, req: &Request, _extensions: &Extensions) -> Option<Self::Key> {
+        let url = req.url();
+        let host = url.host_str()?.to_ascii_lowercase();
+        Some((host, url.port_or_known_default()))
+    }
+}
+
+/// A single bucket shared by every request, see [`RateLimitMiddleware::global`].
+///
+/// [`RateLimitMiddleware::global`]: crate::RateLimitMiddleware::global
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Global;
+
+impl KeyExtractor for Global {
+    type Key = ();
+
+    fn extract(&self, _req: &Request, _extensions: &Extensions) -> Option<()> {
+        Some(())
+    }
+}
@@ -0,0 +1,47 @@
+//! Rate limiting middleware built on [`rquest_middleware`].
+//!
+//! [`RateLimitMiddleware`] keeps requests within a [`Quota`], per host, per custom key or
+//! globally, waiting for a slot or failing fast with [`RateLimitError`]. It also holds off when
+//! the server reports that its own limit is exhausted.
+//!
+//! ## Example
+//!
+//! ```
+//! use std::num::NonZeroU32;
+//! use rquest_middleware::ClientBuilder;
+//! use rquest_ratelimit::{Quota, RateLimitMiddleware};
+//!
+//! async fn run() {
+//!     // At most 10 requests per second to each host.
+//!     let client = ClientBuilder::new(rquest::Client::new())
+//!         .with(RateLimitMiddleware::new(Quota::per_second(
+//!             NonZeroU32::new(10).unwrap(),
+//!         )))
+//!         .build();
+//!
+//!     client.get("https://truelayer.com").send().await.unwrap();
+//! }
+//! ```
+
+mod headers;
+mod key;
+mod middleware;
+mod quota;
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub use key::{ByHost, Global, KeyExtractor};
+pub use middleware::RateLimitMiddleware;
+pub use quota::Quota;
+
+/// Errors returned by [`RateLimitMiddleware`], wrapped in a
+/// [`rquest_middleware::Error::Middleware`].
+#[derive(Debug, Error)]
+pub enum RateLimitError {
+    /// The request would have had to wait longer than allowed by
+    /// [`RateLimitMiddleware::max_wait`].
+    #[error("Rate limit exceeded, retry after {retry_after:?}")]
+    Exceeded { retry_after: Duration },
+}
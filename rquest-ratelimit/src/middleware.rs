@@ -0,0 +1,277 @@
+//! `RateLimitMiddleware` spaces requests out to stay within a [`Quota`].
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use http::Extensions;
+use rquest::Request;
+use rquest_middleware::{Error, Middleware, Next, Response, Result};
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::{sleep, Instant};
+#[cfg(target_arch = "wasm32")]
+use wasmtimer::{std::Instant, tokio::sleep};
+
+use crate::headers::server_backoff;
+use crate::key::{ByHost, Global, KeyExtractor};
+use crate::quota::Quota;
+use crate::RateLimitError;
+
+/// Idle buckets are dropped once there are this many, so that limiting by e.g. host doesn't
+/// grow without bounds.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// `RateLimitMiddleware` delays requests, or fails them, to keep them within a [`Quota`].
+///
+/// Requests draw from buckets selected by a [`KeyExtractor`]: one per host with
+/// [`new`](Self::new), one for every request with [`global`](Self::global), or any custom key
+/// with [`keyed`](Self::keyed). Limits follow the generic cell rate algorithm, which behaves like
+/// a token bucket refilled one token at a time.
+///
+/// By default a request waits until its bucket allows it, use [`fail_fast`](Self::fail_fast) or
+/// [`max_wait`](Self::max_wait) to fail with [`RateLimitError::Exceeded`] instead. The limiter
+/// also holds off when the server says its own limit is exhausted: `Retry-After` on a `429` or
+/// `503`, and `RateLimit` / `RateLimit-*` / `X-RateLimit-*` headers reporting no remaining
+/// requests, see [`adapt_to_headers`](Self::adapt_to_headers).
+///
+/// Waiting uses [`tokio::time::sleep`] on non-`wasm32` archs, so it respects paused time in
+/// tests.
+///
+/// ```
+/// use std::num::NonZeroU32;
+/// use std::time::Duration;
+/// use rquest_middleware::ClientBuilder;
+/// use rquest_ratelimit::{Quota, RateLimitMiddleware};
+///
+/// let client = ClientBuilder::new(rquest::Client::new())
+///     .with(
+///         RateLimitMiddleware::new(Quota::per_second(NonZeroU32::new(10).unwrap()))
+///             .max_wait(Duration::from_secs(5)),
+///     )
+///     .build();
+/// ```
+pub struct RateLimitMiddleware<K: KeyExtractor = ByHost> {
+    quota: Quota,
+    key: K,
+    max_wait: Option<Duration>,
+    adapt_to_headers: bool,
+    buckets: Mutex<HashMap<K::Key, Bucket>>,
+}
+
+impl RateLimitMiddleware<ByHost> {
+    /// Limits requests to each host to `quota`.
+    pub fn new(quota: Quota) -> Self {
+        Self::keyed(quota, ByHost)
+    }
+}
+
+impl RateLimitMiddleware<Global> {
+    /// Limits all requests together to `quota`.
+    pub fn global(quota: Quota) -> Self {
+        Self::keyed(quota, Global)
+    }
+}
+
+impl<K: KeyExtractor> RateLimitMiddleware<K> {
+    /// Limits requests sharing the same key to `quota`.
+    pub fn keyed(quota: Quota, key: K) -> Self {
+        Self {
+            quota,
+            key,
+            max_wait: None,
+            adapt_to_headers: true,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fails requests exceeding the quota right away instead of waiting.
+    pub fn fail_fast(self) -> Self {
+        self.max_wait(Duration::ZERO)
+    }
+
+    /// Fails requests which would have to wait longer than `max_wait`. By default requests wait
+    /// as long as needed.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    /// Whether to hold off when the response headers say the server limit is exhausted.
+    /// Enabled by default.
+    pub fn adapt_to_headers(mut self, adapt: bool) -> Self {
+        self.adapt_to_headers = adapt;
+        self
+    }
+
+    fn acquire(&self, key: K::Key) -> std::result::Result<Duration, Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= PRUNE_THRESHOLD && !buckets.contains_key(&key) {
+            buckets.retain(|_, bucket| !bucket.is_idle(now));
+        }
+        buckets
+            .entry(key)
+            .or_insert_with(|| Bucket::new(now))
+            .acquire(&self.quota, now, self.max_wait)
+    }
+
+    fn hold_off(&self, key: K::Key, backoff: Duration) {
+        let now = Instant::now();
+        // A backoff too far in the future to be represented can't be honoured anyway.
+        let until = match now.checked_add(backoff) {
+            Some(until) => until,
+            None => return,
+        };
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(key).or_insert_with(|| Bucket::new(now));
+        bucket.blocked_until = Some(
+            bucket
+                .blocked_until
+                .map_or(until, |blocked| blocked.max(until)),
+        );
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<K: KeyExtractor> Middleware for RateLimitMiddleware<K> {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let key = match self.key.extract(&req, extensions) {
+            Some(key) => key,
+            None => return next.run(req, extensions).await,
+        };
+
+        match self.acquire(key.clone()) {
+            Ok(wait) if wait > Duration::ZERO => sleep(wait).await,
+            Ok(_) => {}
+            Err(retry_after) => {
+                return Err(Error::middleware(RateLimitError::Exceeded { retry_after }))
+            }
+        }
+
+        let res = next.run(req, extensions).await;
+        if self.adapt_to_headers {
+            if let Ok(res) = &res {
+                if let Some(backoff) = server_backoff(res.status(), res.headers()) {
+                    self.hold_off(key, backoff);
+                }
+            }
+        }
+        res
+    }
+}
+
+/// The state of a bucket for the generic cell rate algorithm.
+#[derive(Debug)]
+struct Bucket {
+    /// The theoretical arrival time: when the bucket would be full again if no other request
+    /// came in.
+    tat: Instant,
+    /// No request is allowed before this, as asked by the server.
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(now: Instant) -> Self {
+        Self {
+            tat: now,
+            blocked_until: None,
+        }
+    }
+
+    /// Reserves a slot for a request at `now`, returning how long to wait for it. Nothing is
+    /// reserved if that's longer than `max_wait`, the wait is returned as an error instead.
+    fn acquire(
+        &mut self,
+        quota: &Quota,
+        now: Instant,
+        max_wait: Option<Duration>,
+    ) -> std::result::Result<Duration, Duration> {
+        let earliest = match self.blocked_until {
+            Some(blocked_until) if blocked_until > now => blocked_until,
+            _ => now,
+        };
+        let interval = quota.replenish_interval();
+        let tolerance = interval.saturating_mul(quota.burst().get());
+
+        // A slot too far in the future to be represented is never available.
+        let tat = match self.tat.max(earliest).checked_add(interval) {
+            Some(tat) => tat,
+            None => return Err(Duration::MAX),
+        };
+        let wait = tat
+            .saturating_duration_since(now)
+            .saturating_sub(tolerance)
+            .max(earliest.saturating_duration_since(now));
+        if max_wait.is_some_and(|max_wait| wait > max_wait) {
+            return Err(wait);
+        }
+        self.tat = tat;
+        Ok(wait)
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        self.tat <= now && !matches!(self.blocked_until, Some(blocked) if blocked > now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroU32;
+
+    #[test]
+    fn allows_bursts_then_spaces_requests() {
+        let quota = Quota::per_second(NonZeroU32::new(2).unwrap());
+        let now = Instant::now();
+        let mut bucket = Bucket::new(now);
+
+        assert_eq!(bucket.acquire(&quota, now, None), Ok(Duration::ZERO));
+        assert_eq!(bucket.acquire(&quota, now, None), Ok(Duration::ZERO));
+        assert_eq!(
+            bucket.acquire(&quota, now, None),
+            Ok(Duration::from_millis(500))
+        );
+        assert_eq!(
+            bucket.acquire(&quota, now, Some(Duration::from_millis(600))),
+            Err(Duration::from_secs(1))
+        );
+
+        let later = now + Duration::from_secs(3);
+        assert_eq!(bucket.acquire(&quota, later, None), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn saturates_quotas_out_of_range() {
+        let now = Instant::now();
+        let quota = Quota::with_period(NonZeroU32::MIN, Duration::from_secs(1 << 50))
+            .allow_burst(NonZeroU32::MAX);
+        let mut bucket = Bucket::new(now);
+        assert_eq!(bucket.acquire(&quota, now, None), Ok(Duration::ZERO));
+        assert_eq!(bucket.acquire(&quota, now, None), Ok(Duration::ZERO));
+
+        let quota = Quota::with_period(NonZeroU32::MIN, Duration::MAX);
+        let mut bucket = Bucket::new(now);
+        assert_eq!(bucket.acquire(&quota, now, None), Err(Duration::MAX));
+    }
+
+    #[test]
+    fn waits_for_the_server() {
+        let quota = Quota::per_second(NonZeroU32::new(2).unwrap());
+        let now = Instant::now();
+        let mut bucket = Bucket::new(now);
+        bucket.blocked_until = Some(now + Duration::from_secs(5));
+
+        assert_eq!(
+            bucket.acquire(&quota, now, None),
+            Ok(Duration::from_secs(5))
+        );
+        assert!(!bucket.is_idle(now + Duration::from_secs(4)));
+        assert!(bucket.is_idle(now + Duration::from_secs(6)));
+    }
+}
@@ -0,0 +1,73 @@
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+/// How many requests are allowed over a period of time.
+///
+/// Requests are spread evenly over the period, one every `period / limit`, with bursts of up to
+/// `limit` requests allowed after a quiet period. Use [`Quota::allow_burst`] to change the size
+/// of the bursts.
+///
+/// ```
+/// use std::num::NonZeroU32;
+/// use std::time::Duration;
+/// use rquest_ratelimit::Quota;
+///
+/// // 100 requests per minute, at most 10 at once.
+/// let quota = Quota::per_minute(NonZeroU32::new(100).unwrap())
+///     .allow_burst(NonZeroU32::new(10).unwrap());
+/// assert_eq!(quota.replenish_interval(), Duration::from_millis(600));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    replenish_interval: Duration,
+    burst: NonZeroU32,
+}
+
+impl Quota {
+    /// `limit` requests per second.
+    pub fn per_second(limit: NonZeroU32) -> Self {
+        Self::with_period(limit, Duration::from_secs(1))
+    }
+
+    /// `limit` requests per minute.
+    pub fn per_minute(limit: NonZeroU32) -> Self {
+        Self::with_period(limit, Duration::from_secs(60))
+    }
+
+    /// `limit` requests per hour.
+    pub fn per_hour(limit: NonZeroU32) -> Self {
+        Self::with_period(limit, Duration::from_secs(60 * 60))
+    }
+
+    /// `limit` requests every `period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn with_period(limit: NonZeroU32, period: Duration) -> Self {
+        assert!(
+            period > Duration::ZERO,
+            "the period of a quota can't be zero"
+        );
+        Self {
+            replenish_interval: period / limit.get(),
+            burst: limit,
+        }
+    }
+
+    /// Allows bursts of up to `burst` requests instead of the limit given to the constructor.
+    pub fn allow_burst(mut self, burst: NonZeroU32) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    /// The time it takes to earn the right to send one more request.
+    pub fn replenish_interval(&self) -> Duration {
+        self.replenish_interval
+    }
+
+    /// The number of requests which can be sent at once.
+    pub fn burst(&self) -> NonZeroU32 {
+        self.burst
+    }
+}
@@ -0,0 +1,111 @@
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use rquest_middleware::{ClientBuilder, ClientWithMiddleware, Error, Middleware, Response};
+use rquest_mock::matchers::method;
+use rquest_mock::{Mock, MockTransport, RecordedRequest};
+use rquest_ratelimit::{Quota, RateLimitError, RateLimitMiddleware};
+use tokio::time::Instant;
+
+fn client<M: Middleware>(transport: &MockTransport, rate_limit: M) -> ClientWithMiddleware {
+    ClientBuilder::new(rquest::Client::new())
+        .with(rate_limit)
+        .with_transport(transport.clone())
+        .build()
+}
+
+fn ok(transport: &MockTransport) {
+    transport.mount(Mock::given(method("GET")).respond_with(Response::builder().body("").unwrap()));
+}
+
+fn two_per_second() -> Quota {
+    Quota::per_second(NonZeroU32::new(2).unwrap())
+}
+
+#[tokio::test(start_paused = true)]
+async fn waits_for_a_slot() {
+    let transport = MockTransport::new();
+    ok(&transport);
+    let client = client(&transport, RateLimitMiddleware::new(two_per_second()));
+
+    let start = Instant::now();
+    for _ in 0..3 {
+        client.get("http://a.com/").send().await.unwrap();
+    }
+    assert_eq!(start.elapsed(), Duration::from_millis(500));
+
+    // Other hosts have their own bucket.
+    client.get("http://b.com/").send().await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_millis(500));
+}
+
+#[tokio::test(start_paused = true)]
+async fn fails_fast() {
+    let transport = MockTransport::new();
+    ok(&transport);
+    let client = client(
+        &transport,
+        RateLimitMiddleware::global(two_per_second()).fail_fast(),
+    );
+
+    client.get("http://a.com/").send().await.unwrap();
+    client.get("http://b.com/").send().await.unwrap();
+    let err = client.get("http://a.com/").send().await.unwrap_err();
+
+    let Error::Middleware(err) = err else {
+        panic!("unexpected error {err:?}");
+    };
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(RateLimitError::Exceeded { retry_after }) if *retry_after == Duration::from_millis(500)
+    ));
+    assert_eq!(transport.received_requests().len(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn holds_off_when_the_server_says_so() {
+    let calls = AtomicU32::new(0);
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET"))
+            .respond_with(move |_: &RecordedRequest| {
+                let builder = match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Response::builder().status(429).header("retry-after", "10"),
+                    _ => Response::builder(),
+                };
+                builder.body("")
+            })
+            .expect(2),
+    );
+    let client = client(&transport, RateLimitMiddleware::new(two_per_second()));
+
+    let start = Instant::now();
+    let res = client.get("http://a.com/").send().await.unwrap();
+    assert_eq!(res.status(), 429);
+    let res = client.get("http://a.com/").send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(start.elapsed(), Duration::from_secs(10));
+
+    transport.verify();
+}
+
+#[tokio::test(start_paused = true)]
+async fn ignores_backoffs_out_of_range() {
+    let transport = MockTransport::new();
+    transport.mount(
+        Mock::given(method("GET")).respond_with(
+            Response::builder()
+                .status(429)
+                .header("retry-after", u64::MAX.to_string())
+                .body("")
+                .unwrap(),
+        ),
+    );
+    let client = client(&transport, RateLimitMiddleware::new(two_per_second()));
+
+    for _ in 0..2 {
+        let res = client.get("http://a.com/").send().await.unwrap();
+        assert_eq!(res.status(), 429);
+    }
+}
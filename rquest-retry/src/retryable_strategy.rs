@@ -111,7 +111,10 @@ impl RetryableStrategy for DefaultRetryableStrategy {
 ///
 /// Note that success here means that the request finished without interruption, not that it was logically OK.
 pub fn default_on_request_success(success: &rquest_middleware::Response) -> Option<Retryable> {
-    let status = success.status();
+    classify_status(success.status())
+}
+
+fn classify_status(status: StatusCode) -> Option<Retryable> {
     if status.is_server_error() {
         Some(Retryable::Transient)
     } else if status.is_client_error()
@@ -130,11 +133,16 @@ pub fn default_on_request_success(success: &rquest_middleware::Response) -> Opti
 
 /// Default request failure retry strategy.
 ///
-/// Will only retry if the request failed due to a network error
+/// Will only retry if the request failed due to a network error, or if it failed with an
+/// [`Error::Status`] (see [`Response::error_for_status`]) that [`default_on_request_success`]
+/// would retry.
+///
+/// [`Response::error_for_status`]: rquest_middleware::Response::error_for_status
 pub fn default_on_request_failure(error: &Error) -> Option<Retryable> {
     match error {
         // If something fails in the middleware we're screwed.
         Error::Middleware(_) => Some(Retryable::Fatal),
+        Error::Status(error) => classify_status(error.status()),
         Error::Rquest(error) => {
             #[cfg(not(target_arch = "wasm32"))]
             let is_connect = error.is_connect();
@@ -361,3 +361,85 @@ async fn assert_retry_on_connection_reset_by_peer() {
 
     assert_eq!(resp.status(), 200);
 }
+
+struct ErrorForStatusMiddleware;
+
+#[async_trait::async_trait]
+impl rquest_middleware::Middleware for ErrorForStatusMiddleware {
+    async fn handle(
+        &self,
+        req: rquest::Request,
+        extensions: &mut http::Extensions,
+        next: rquest_middleware::Next<'_>,
+    ) -> rquest_middleware::Result<rquest_middleware::Response> {
+        next.run(req, extensions).await?.error_for_status().await
+    }
+}
+
+#[tokio::test]
+async fn assert_retry_on_transient_status_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/foo"))
+        .respond_with(RetryResponder::new(3, 503))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let client = ClientBuilder::new(Client::builder().build().unwrap())
+        .with(RetryTransientMiddleware::new_with_policy(
+            ExponentialBackoff::builder()
+                .retry_bounds(
+                    std::time::Duration::from_millis(30),
+                    std::time::Duration::from_millis(100),
+                )
+                .build_with_max_retries(3),
+        ))
+        .with(ErrorForStatusMiddleware)
+        .build();
+
+    let resp = client
+        .get(format!("{}/foo", server.uri()))
+        .send()
+        .await
+        .expect("call failed");
+
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn assert_no_retry_on_fatal_status_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/foo"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("missing"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ClientBuilder::new(Client::builder().build().unwrap())
+        .with(RetryTransientMiddleware::new_with_policy(
+            ExponentialBackoff::builder().build_with_max_retries(3),
+        ))
+        .with(ErrorForStatusMiddleware)
+        .build();
+
+    let err = client
+        .get(format!("{}/foo", server.uri()))
+        .send()
+        .await
+        .expect_err("call should fail");
+
+    let rquest_middleware::Error::Middleware(err) = err else {
+        panic!("unexpected error {err:?}");
+    };
+    let Some(rquest_retry::RetryError::Error(err)) = err.downcast_ref() else {
+        panic!("unexpected error {err:?}");
+    };
+    assert!(err.is_status());
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+    let rquest_middleware::Error::Status(status_error) = err else {
+        panic!("unexpected error {err:?}");
+    };
+    assert_eq!(status_error.body().as_ref(), b"missing");
+}
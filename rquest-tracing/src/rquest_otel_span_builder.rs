@@ -71,7 +71,12 @@ pub fn default_on_request_success(span: &Span, response: &Response) {
     }
     span.record(HTTP_RESPONSE_STATUS_CODE, response.status().as_u16());
 
-    let body = response.clone().text().unwrap_or("failted to read body".into());
+    // Only log the body if some middleware already buffered it, we never force a streaming
+    // body into memory just for tracing.
+    let body = response
+        .buffered_bytes()
+        .map(|bytes| String::from_utf8_lossy(bytes))
+        .unwrap_or_else(|| "<streaming body>".into());
 
     span.in_scope(|| {
         tracing::debug!(headers = ?response.headers(), body = %body, "response");
@@ -93,13 +98,11 @@ pub fn default_on_request_failure(span: &Span, e: &Error) {
     span.record(OTEL_STATUS_CODE, "ERROR");
     span.record(ERROR_MESSAGE, error_message.as_str());
     span.record(ERROR_CAUSE_CHAIN, error_cause_chain.as_str());
-    if let Error::Rquest(e) = e {
-        if let Some(status) = e.status() {
-            span.record(HTTP_RESPONSE_STATUS_CODE, status.as_u16());
-            #[cfg(feature = "deprecated_attributes")]
-            {
-                span.record(HTTP_STATUS_CODE, status.as_u16());
-            }
+    if let Some(status) = e.status() {
+        span.record(HTTP_RESPONSE_STATUS_CODE, status.as_u16());
+        #[cfg(feature = "deprecated_attributes")]
+        {
+            span.record(HTTP_STATUS_CODE, status.as_u16());
         }
     }
 }